@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
 use std::ops::Range;
 use std::str::FromStr;
 use std::sync::LazyLock;
@@ -162,3 +163,39 @@ impl AnimeRelationRule {
         }
     }
 }
+
+impl Display for AnimeRelationRule {
+    /// Formats the rule the same way as in the anime-relations file (with
+    /// IDs given as MAL|Kitsu|AniList).
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let ids = |dst: bool| {
+            [AnimeDbs::Mal, AnimeDbs::Kitsu, AnimeDbs::AniList]
+                .iter()
+                .map(|db| {
+                    self.db_mappings
+                        .get(db)
+                        .map_or("?".to_owned(), |(src, dst_id)| {
+                            if dst { dst_id } else { src }.to_string()
+                        })
+                })
+                .collect::<Vec<_>>()
+                .join("|")
+        };
+        let range = |range: &Range<i32>| {
+            if range.len() == 1 {
+                range.start.to_string()
+            } else {
+                format!("{}-{}", range.start, range.end - 1)
+            }
+        };
+
+        write!(
+            f,
+            "{}:{} -> {}:{}",
+            ids(false),
+            range(&self.range_src),
+            ids(true),
+            range(&self.range_dst)
+        )
+    }
+}
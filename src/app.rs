@@ -1,56 +1,128 @@
 use std::collections::{HashMap, HashSet};
-use std::sync::{Arc, RwLock};
+use std::path::Path;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
 
 use anyhow::Context;
-use log::{error, info};
+use log::{debug, error, info, warn};
 use notify_rust::Notification;
-use tokio::time;
+use tokio::{task, time};
+use url::Url;
 
 use crate::anime_relations::AnimeRelations;
 use crate::clients::mal_client::{MalAuthenticator, MalClient, MalClientResult};
-use crate::clients::{AnimeDbClient, AnimeInfo};
-use crate::config::Config;
-use crate::constants::REFRESH_INTERVAL;
-use crate::player_controller::{Player, PlayerController};
-use crate::title_recognizer::{Title, TitleRecognizer};
+use crate::clients::{AnimeDbClient, AnimeId, AnimeInfo};
+use crate::config::{Config, IgnoreConfig};
+use crate::constants::{IDLE_REFRESH_INTERVAL, PLAYER_TIMEOUT, REFRESH_INTERVAL};
+use crate::learned_mappings::LearnedMappings;
+use crate::offline_database::OfflineDatabase;
+use crate::player_controller::{
+    MediaPlayer, PlayerController, PlayerEvents, PlayerSource, filename_from_url,
+};
+use crate::title_recognizer::{PlayedMedia, Title, TitleRecognizer};
+use crate::watch_session::WatchSession;
+use crate::webhook_server;
 
 #[derive(Clone)]
 pub struct PlayedTitle {
+    pub title: Title,
     pub anime_info: AnimeInfo,
     pub player_name: String,
     pub scrobbled: bool,
     pub should_scrobble: bool,
+    /// Real time the episode has to be played for before it is scrobbled,
+    /// taking the playback rate into account
+    pub time_until_scrobble: Option<Duration>,
+    /// Whether the match confidence is below the configured threshold.
+    pub uncertain: bool,
 }
 
+/// A player that is playing something recognized as an anime episode.
+struct ActivePlayer {
+    name: String,
+    title: Title,
+    media: PlayedMedia,
+    position: Duration,
+    rate: f64,
+    /// Whether the player has reported the media as watched
+    watched: bool,
+}
+
+/// The state of the app is behind short-lived locks rather than the whole app
+/// being locked, so that the GUI can use the app while the players are being
+/// checked or MAL is being queried.
 pub struct TundraApp {
     config: Arc<RwLock<Config>>,
-    player_controller: PlayerController,
-    title_recognizer: TitleRecognizer,
-    mal_client: MalClient,
-    scrobbled_titles: HashSet<AnimeInfo>,
-    anime_info_cache: HashMap<Title, Option<AnimeInfo>>,
+    player_source: Box<dyn PlayerSource>,
+    /// Shared with the blocking tasks in which the titles are recognized, as
+    /// some recognizers read the played files
+    title_recognizer: Arc<Mutex<TitleRecognizer>>,
+    anime_db_client: Box<dyn AnimeDbClient>,
+    learned_mappings: Arc<RwLock<LearnedMappings>>,
+    /// Anime IDs and episode numbers of the episodes scrobbled so far
+    scrobbled_titles: Mutex<HashSet<(AnimeId, i32)>>,
+    anime_info_cache: Mutex<HashMap<Title, Option<AnimeInfo>>>,
+    /// Watch sessions by the IDs of the players
+    watch_sessions: Mutex<HashMap<String, WatchSession>>,
 }
 
 impl TundraApp {
     pub fn init() -> anyhow::Result<Self> {
         let config = Arc::new(RwLock::new(Config::load()));
         let anime_relations = Arc::new(AnimeRelations::new());
-        let player_controller = PlayerController::new()?;
-        let title_recognizer = TitleRecognizer::new();
-        let mal_client = MalClient::new(config.clone(), anime_relations)?;
-        let scrobbled_titles = Default::default();
+        let offline_database = Arc::new(Self::load_offline_database(&config.read().unwrap()));
+        let learned_mappings = Arc::new(RwLock::new(LearnedMappings::load()));
+        let player_controller = PlayerController::new(config.clone())?;
+        let mal_client = MalClient::new(
+            config.clone(),
+            anime_relations,
+            offline_database,
+            learned_mappings.clone(),
+        )?;
 
-        Ok(Self {
+        Ok(Self::new(
             config,
-            player_controller,
+            Box::new(player_controller),
+            Box::new(mal_client),
+            learned_mappings,
+        ))
+    }
+
+    fn new(
+        config: Arc<RwLock<Config>>,
+        player_source: Box<dyn PlayerSource>,
+        anime_db_client: Box<dyn AnimeDbClient>,
+        learned_mappings: Arc<RwLock<LearnedMappings>>,
+    ) -> Self {
+        let title_recognizer = Arc::new(Mutex::new(TitleRecognizer::new(
+            &config.read().unwrap().recognition,
+        )));
+
+        Self {
+            config,
+            player_source,
             title_recognizer,
-            mal_client,
-            scrobbled_titles,
-            anime_info_cache: HashMap::new(),
+            anime_db_client,
+            learned_mappings,
+            scrobbled_titles: Default::default(),
+            anime_info_cache: Default::default(),
+            watch_sessions: Default::default(),
+        }
+    }
+
+    fn load_offline_database(config: &Config) -> OfflineDatabase {
+        let path = config.offline_database_path();
+        if config.offline_database.path.is_none() && !path.exists() {
+            return OfflineDatabase::default();
+        }
+
+        OfflineDatabase::load(&path).unwrap_or_else(|e| {
+            warn!("{e:?}");
+            OfflineDatabase::default()
         })
     }
 
-    pub async fn authenticate_mal_cli(&mut self) -> anyhow::Result<()> {
+    pub async fn authenticate_mal_cli(&self) -> anyhow::Result<()> {
         let authenticator = self.start_mal_authentication().await?;
         info!(
             "Visit this website to authenticate: {}",
@@ -62,9 +134,9 @@ impl TundraApp {
         Ok(())
     }
 
-    pub async fn start_mal_authentication(&mut self) -> MalClientResult<MalAuthenticator> {
-        self.scrobbled_titles.clear();
-        self.mal_client.start_authentication().await
+    pub async fn start_mal_authentication(&self) -> MalClientResult<MalAuthenticator> {
+        self.scrobbled_titles.lock().unwrap().clear();
+        MalAuthenticator::start_authentication(self.config.clone()).await
     }
 
     pub fn is_mal_authenticated(&self) -> bool {
@@ -79,109 +151,462 @@ impl TundraApp {
         );
     }
 
-    pub async fn run_daemon(&mut self) {
-        let mut interval = time::interval(REFRESH_INTERVAL);
+    /// Recognizes given filename, URL or title and prints how it was done,
+    /// optionally also resolving it to a MAL entry. Nothing is scrobbled.
+    pub async fn recognize_cli(&self, input: &str, resolve: bool) -> anyhow::Result<()> {
+        let media = Self::media_from_input(input);
+        println!("Input: {media:?}");
+        if self.config.read().unwrap().ignore.is_media_ignored(&media) {
+            println!("This media is ignored by the configured ignore rules");
+        }
 
-        loop {
-            interval.tick().await;
-            let scrobble_result = self.try_scrobble().await;
-            if let Err(err) = scrobble_result {
-                error!("{err:?}");
+        if let Some(elements) = media
+            .filename
+            .as_deref()
+            .and_then(TitleRecognizer::anitomy_elements)
+        {
+            println!("\nAnitomy elements:");
+            for element in elements.iter() {
+                println!("  {:?}: {}", element.category, element.value);
             }
         }
-    }
 
-    async fn get_scrobblable_title(&mut self) -> anyhow::Result<Option<(Title, String, bool)>> {
-        info!("Checking active players");
+        println!("\nRecognizers:");
+        let steps = self.title_recognizer.lock().unwrap().recognize_all(&media);
+        for step in &steps {
+            match &step.title {
+                Some(title) => println!(
+                    "  {} (confidence {}): {title:?}",
+                    step.recognizer, step.confidence
+                ),
+                None => println!("  {}: no match", step.recognizer),
+            }
+        }
 
-        let players = self.player_controller.get_players()?;
-        for player in players {
-            if let Some(title) = Self::check_player(&mut self.title_recognizer, &player)? {
-                let player_name = player.player_name()?;
-                let should_scrobble = player.position()? > 0.5;
-                info!(
-                    "Found an active player: {}, playing {} season {} episode {}",
-                    player_name, title.title, title.season_number, title.episode_number
+        let Some(step) = self.title_recognizer.lock().unwrap().choose(&steps) else {
+            println!("\nThe title was not recognized");
+            return Ok(());
+        };
+        let title = step.title.clone().expect("chosen steps have a title");
+        println!("\nMatched by {}: {title:?}", step.recognizer);
+
+        if resolve {
+            self.check_mal_authenticated();
+            let explanation = self.anime_db_client.explain(&title).await?;
+
+            println!("\nMAL search results:");
+            for result in &explanation.search_results {
+                println!(
+                    "  {} (ID {}): relevance {:.3}, title similarity {:.2}",
+                    result.title, result.id, result.relevance, result.title_similarity
+                );
+            }
+
+            println!("\nCandidates:");
+            for candidate in &explanation.candidates {
+                println!(
+                    "  {} (ID {}) episode {}: confidence {}",
+                    candidate.title, candidate.id, candidate.episode_number, candidate.confidence
                 );
-                return Ok(Some((title, player_name, should_scrobble)));
+                if let Some(rule) = &candidate.relation_rule {
+                    println!("    anime-relations rule applied: {rule}");
+                }
+            }
+
+            match explanation.result {
+                Some(result) => println!(
+                    "\nResult: {} (ID {}) episode {}, confidence {}",
+                    result.title, result.id, result.episode_number, result.confidence
+                ),
+                None => println!("\nResult: no match"),
             }
         }
 
-        Ok(None)
+        Ok(())
+    }
+
+    /// Builds the media info a player would report when playing given file,
+    /// URL or title.
+    fn media_from_input(input: &str) -> PlayedMedia {
+        let path = Path::new(input);
+        if let Ok(path) = path.canonicalize() {
+            let filename = path.file_name().map(|x| x.to_string_lossy().into_owned());
+            return PlayedMedia {
+                title: filename.clone(),
+                filename,
+                url: Url::from_file_path(&path).ok().map(String::from),
+                ..Default::default()
+            };
+        }
+
+        if Url::parse(input).is_ok_and(|x| x.has_host()) {
+            return PlayedMedia {
+                filename: filename_from_url(input),
+                url: Some(input.to_owned()),
+                ..Default::default()
+            };
+        }
+
+        PlayedMedia {
+            title: Some(input.to_owned()),
+            filename: Some(input.to_owned()),
+            ..Default::default()
+        }
+    }
+
+    pub async fn run_daemon(&self) {
+        if let Err(e) = self.start_webhook_server().await {
+            error!("{e:?}");
+        }
+        let mut player_events = self.player_events().await;
+
+        loop {
+            let interval = match self.try_scrobble().await {
+                Ok(played_titles) => Self::refresh_interval(&player_events, &played_titles),
+                Err(err) => {
+                    error!("{err:?}");
+                    REFRESH_INTERVAL
+                }
+            };
+            player_events.wait(interval).await;
+        }
     }
 
-    fn check_player(
-        title_recognizer: &mut TitleRecognizer,
-        player: &Player,
-    ) -> anyhow::Result<Option<Title>> {
-        if player.is_currently_playing()? {
-            let title = player.title_played().ok();
-            let filename = player.filename_played().ok();
-            let title = title_recognizer.recognize(title.as_deref(), filename.as_deref());
+    /// Starts receiving the webhooks of media servers, if configured.
+    pub async fn start_webhook_server(&self) -> anyhow::Result<()> {
+        let webhook_config = self.config.read().unwrap().webhook.clone();
+        webhook_server::start(webhook_config, self.player_source.remote_playbacks()).await
+    }
 
-            Ok(title)
+    /// Subscribes to the events of the players, so that the players are
+    /// checked again as soon as something changes.
+    pub async fn player_events(&self) -> PlayerEvents {
+        self.player_source.events().await
+    }
+
+    /// Returns how long to wait for player events before checking the players
+    /// again. The players only need to be polled while there is an episode
+    /// that is not scrobbled yet, since the playback position does not emit
+    /// any events.
+    pub fn refresh_interval(
+        player_events: &PlayerEvents,
+        played_titles: &[PlayedTitle],
+    ) -> Duration {
+        let waiting_for_scrobble = played_titles.iter().any(|x| !x.scrobbled);
+
+        if !player_events.is_polling_required() && !waiting_for_scrobble {
+            IDLE_REFRESH_INTERVAL
         } else {
-            Ok(None)
+            REFRESH_INTERVAL
         }
     }
 
-    pub async fn get_played_title(&mut self) -> anyhow::Result<Option<PlayedTitle>> {
-        let result = self.get_scrobblable_title().await?;
+    async fn get_scrobblable_titles(
+        &self,
+    ) -> anyhow::Result<Vec<(Title, String, bool, Option<Duration>)>> {
+        info!("Checking active players");
 
-        if let Some((title, player_name, should_scrobble)) = result {
-            let anime_info = self.anime_info_for_title(title).await?;
+        let ignore_config = self.config.read().unwrap().ignore.clone();
+        let scrobble_config = self.config.read().unwrap().scrobble.clone();
+        let players = self.player_source.get_players().await?;
 
-            if let Some(anime_info) = anime_info {
-                let scrobbled = self.scrobbled_titles.contains(&anime_info);
-                Ok(Some(PlayedTitle {
-                    anime_info,
-                    player_name,
-                    scrobbled,
-                    should_scrobble,
-                }))
+        // Sessions of paused players are kept, so that they can be resumed
+        self.watch_sessions
+            .lock()
+            .unwrap()
+            .retain(|id, _| players.iter().any(|x| x.id() == id));
+
+        let mut titles = Vec::new();
+        for player in players {
+            let result = time::timeout(
+                PLAYER_TIMEOUT,
+                Self::check_player(&self.title_recognizer, &ignore_config, player.as_ref()),
+            )
+            .await;
+            let active_player = match result {
+                Ok(Ok(Some(active_player))) => active_player,
+                Ok(Ok(None)) => {
+                    self.pause_watch_session(player.as_ref()).await;
+                    continue;
+                }
+                Ok(Err(e)) => {
+                    warn!("Could not check player {}: {e:?}", player.id());
+                    continue;
+                }
+                Err(_) => {
+                    warn!(
+                        "Player {} did not respond in time, skipping it",
+                        player.id()
+                    );
+                    continue;
+                }
+            };
+
+            // Media reported as watched are scrobbled regardless of the time
+            // they have been watched for, so their duration is not needed
+            let (should_scrobble, time_until_scrobble, progress) = if active_player.watched {
+                (true, None, "reported as watched".to_owned())
             } else {
-                Ok(None)
+                let (duration, watched, rate) = {
+                    let mut watch_sessions = self.watch_sessions.lock().unwrap();
+                    match Self::update_watch_session(
+                        &mut watch_sessions,
+                        player.id(),
+                        &active_player,
+                    ) {
+                        Ok(session) => (session.duration(), session.watched(), session.rate()),
+                        Err(e) => {
+                            warn!("Could not track player {}: {e:?}", player.id());
+                            continue;
+                        }
+                    }
+                };
+                (
+                    scrobble_config.should_scrobble(duration, watched),
+                    scrobble_config
+                        .remaining_watch_time(duration, watched)
+                        .map(|x| x.div_f64(rate)),
+                    format!("watched for {}s", watched.as_secs()),
+                )
+            };
+            let ActivePlayer { name, title, .. } = active_player;
+            info!(
+                "Found an active player: {}, playing {} season {} episode {} ({progress})",
+                name, title.title, title.season_number, title.episode_number,
+            );
+            titles.push((title, name, should_scrobble, time_until_scrobble));
+        }
+
+        Ok(titles)
+    }
+
+    /// Pauses the watch session of given player, which is not playing
+    /// anything now, if it has one.
+    async fn pause_watch_session(&self, player: &dyn MediaPlayer) {
+        if !self
+            .watch_sessions
+            .lock()
+            .unwrap()
+            .contains_key(player.id())
+        {
+            return;
+        }
+
+        let position = time::timeout(PLAYER_TIMEOUT, player.position())
+            .await
+            .ok()
+            .and_then(Result::ok);
+        if let Some(session) = self.watch_sessions.lock().unwrap().get_mut(player.id()) {
+            session.pause(position);
+        }
+    }
+
+    /// Updates the watch session of given player with its current position,
+    /// or starts a new one if another track is being played now.
+    fn update_watch_session<'a>(
+        watch_sessions: &'a mut HashMap<String, WatchSession>,
+        player_id: &str,
+        active_player: &ActivePlayer,
+    ) -> anyhow::Result<&'a WatchSession> {
+        let ActivePlayer {
+            name,
+            media,
+            position,
+            rate,
+            ..
+        } = active_player;
+        let track = media
+            .url
+            .clone()
+            .or_else(|| media.title.clone())
+            .unwrap_or_default();
+
+        let session = match watch_sessions.remove(player_id) {
+            Some(mut session) if session.is_for(&track) => {
+                session.update(*position, *rate);
+                session
             }
-        } else {
-            Ok(None)
+            _ => {
+                let duration = media
+                    .duration
+                    .context("Could not get the duration of the played media")?;
+                debug!("Starting a new watch session for {track} played by {name}");
+                WatchSession::new(track, duration, *position, *rate)
+            }
+        };
+
+        Ok(watch_sessions
+            .entry(player_id.to_owned())
+            .or_insert(session))
+    }
+
+    async fn check_player(
+        title_recognizer: &Arc<Mutex<TitleRecognizer>>,
+        ignore_config: &IgnoreConfig,
+        player: &dyn MediaPlayer,
+    ) -> anyhow::Result<Option<ActivePlayer>> {
+        if !player.is_currently_playing().await? {
+            return Ok(None);
         }
+
+        let player_name = player.player_name().await?;
+        let desktop_entry = player.desktop_entry().await.ok();
+        if ignore_config.is_player_ignored(&player_name, desktop_entry.as_deref()) {
+            debug!("Ignoring player {player_name}");
+            return Ok(None);
+        }
+
+        let media = PlayedMedia {
+            title: player.title_played().await.ok(),
+            filename: player.filename_played().await.ok(),
+            url: player.url_played().await.ok(),
+            duration: player.duration().await.ok(),
+            metadata: player.metadata().await.unwrap_or_default(),
+        };
+        if ignore_config.is_media_ignored(&media) {
+            debug!("Ignoring media played by {player_name}: {media:?}");
+            return Ok(None);
+        }
+
+        let title = match player.title().await? {
+            Some(title) => {
+                debug!("Using the title reported by {player_name}: {title:?}");
+                TitleRecognizer::post_process(title)
+            }
+            None => {
+                let title_recognizer = title_recognizer.clone();
+                let media = media.clone();
+                let title = task::spawn_blocking(move || {
+                    title_recognizer.lock().unwrap().recognize(&media)
+                })
+                .await?;
+                match title {
+                    Some(title) => title,
+                    None => return Ok(None),
+                }
+            }
+        };
+        let position = player.position().await?;
+        // Not all players report the rate
+        let rate = player.rate().await.unwrap_or(1.0);
+        let watched = player.is_watched().await?;
+
+        Ok(Some(ActivePlayer {
+            name: player_name,
+            title,
+            media,
+            position,
+            rate,
+            watched,
+        }))
     }
 
-    async fn anime_info_for_title(&mut self, title: Title) -> anyhow::Result<Option<AnimeInfo>> {
-        if self.anime_info_cache.contains_key(&title) {
-            return Ok(self.anime_info_cache[&title].clone());
+    pub async fn get_played_titles(&self) -> anyhow::Result<Vec<PlayedTitle>> {
+        let mut played_titles = Vec::new();
+
+        for (title, player_name, should_scrobble, time_until_scrobble) in
+            self.get_scrobblable_titles().await?
+        {
+            let Some(anime_info) = self.anime_info_for_title(title.clone()).await? else {
+                continue;
+            };
+
+            let scrobbled = self
+                .scrobbled_titles
+                .lock()
+                .unwrap()
+                .contains(&anime_info.episode_key());
+            let min_confidence = self.config.read().unwrap().matching.min_confidence;
+            let uncertain = anime_info.confidence.0 < min_confidence;
+            played_titles.push(PlayedTitle {
+                title,
+                anime_info,
+                player_name,
+                scrobbled,
+                should_scrobble,
+                time_until_scrobble,
+                uncertain,
+            });
         }
 
-        let anime_info = self.mal_client.get_anime_info(&title).await?;
-        self.anime_info_cache.insert(title, anime_info.clone());
+        Ok(played_titles)
+    }
+
+    async fn anime_info_for_title(&self, title: Title) -> anyhow::Result<Option<AnimeInfo>> {
+        if let Some(anime_info) = self.anime_info_cache.lock().unwrap().get(&title) {
+            return Ok(anime_info.clone());
+        }
+
+        let anime_info = self.anime_db_client.get_anime_info(&title).await?;
+        self.anime_info_cache
+            .lock()
+            .unwrap()
+            .insert(title, anime_info.clone());
 
         Ok(anime_info)
     }
 
-    pub async fn try_scrobble(&mut self) -> anyhow::Result<()> {
-        let title = self.get_played_title().await?;
+    /// Remembers that given title is the anime with given ID, either because
+    /// the user has confirmed the match or because they have corrected it.
+    pub fn remember_match(&self, title: &Title, anime_id: AnimeId) -> anyhow::Result<()> {
+        let learned_mappings = self
+            .learned_mappings
+            .write()
+            .unwrap()
+            .insert(title, anime_id);
+        learned_mappings.save()?;
+        self.anime_info_cache
+            .lock()
+            .unwrap()
+            .retain(|x, _| !LearnedMappings::same_key(x, title));
+        self.anime_db_client.clear_cached_title(title);
 
-        if let Some(title) = title
-            && title.should_scrobble
-        {
+        Ok(())
+    }
+
+    /// Scrobbles the currently played titles that should be scrobbled, and
+    /// returns all of them.
+    pub async fn try_scrobble(&self) -> anyhow::Result<Vec<PlayedTitle>> {
+        let mut titles = self.get_played_titles().await?;
+
+        for title in &mut titles {
+            if !title.should_scrobble {
+                continue;
+            }
+
+            // Another player might have just scrobbled the same episode
+            title.scrobbled |= self
+                .scrobbled_titles
+                .lock()
+                .unwrap()
+                .contains(&title.anime_info.episode_key());
             if title.scrobbled {
                 info!("Already scrobbled, skipping...");
+            } else if title.uncertain {
+                info!(
+                    "Match {} is uncertain (confidence {}), skipping...",
+                    title.anime_info.title, title.anime_info.confidence
+                );
             } else {
                 self.scrobble_title(&title.anime_info).await?;
+                title.scrobbled = true;
             }
         }
 
-        Ok(())
+        Ok(titles)
     }
 
-    async fn scrobble_title(&mut self, anime_info: &AnimeInfo) -> anyhow::Result<()> {
+    async fn scrobble_title(&self, anime_info: &AnimeInfo) -> anyhow::Result<()> {
         info!(
             "Scrobbling {} episode {} / {}",
             anime_info.title, anime_info.episode_watched, anime_info.total_episodes
         );
 
-        let scrobbled = self.mal_client.set_title_watched(anime_info).await?;
-        self.scrobbled_titles.insert(anime_info.clone());
+        let scrobbled = self.anime_db_client.set_title_watched(anime_info).await?;
+        self.scrobbled_titles
+            .lock()
+            .unwrap()
+            .insert(anime_info.episode_key());
 
         if scrobbled {
             Notification::new()
@@ -201,3 +626,357 @@ impl TundraApp {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::clients::{Confidence, MatchExplanation, PictureUrl, WebsiteUrl};
+    use crate::player_controller::RemotePlaybacks;
+
+    const DURATION: Duration = Duration::from_secs(24 * 60);
+    const FILENAME: &str = "[Group] Sousou no Frieren - 05 [1080p].mkv";
+
+    /// A player playing given file, as scripted by the test.
+    #[derive(Clone)]
+    struct FakePlayer {
+        id: String,
+        name: String,
+        url: String,
+        duration: Option<Duration>,
+        position: Duration,
+        rate: f64,
+        playing: bool,
+        watched: bool,
+    }
+
+    impl FakePlayer {
+        fn new(id: &str) -> Self {
+            Self {
+                id: id.to_owned(),
+                name: "Fake Player".to_owned(),
+                url: format!("file:///home/user/Videos/{FILENAME}"),
+                duration: Some(DURATION),
+                position: Duration::ZERO,
+                rate: 1.0,
+                playing: true,
+                watched: false,
+            }
+        }
+
+        fn watched(mut self) -> Self {
+            self.watched = true;
+            self
+        }
+    }
+
+    #[async_trait]
+    impl MediaPlayer for FakePlayer {
+        fn id(&self) -> &str {
+            &self.id
+        }
+
+        async fn player_name(&self) -> anyhow::Result<String> {
+            Ok(self.name.clone())
+        }
+
+        async fn desktop_entry(&self) -> anyhow::Result<String> {
+            Ok("fake-player".to_owned())
+        }
+
+        async fn is_currently_playing(&self) -> anyhow::Result<bool> {
+            Ok(self.playing)
+        }
+
+        async fn is_watched(&self) -> anyhow::Result<bool> {
+            Ok(self.watched)
+        }
+
+        async fn title_played(&self) -> anyhow::Result<String> {
+            Ok(FILENAME.to_owned())
+        }
+
+        async fn url_played(&self) -> anyhow::Result<String> {
+            Ok(self.url.clone())
+        }
+
+        async fn filename_played(&self) -> anyhow::Result<String> {
+            filename_from_url(&self.url).context("No filename")
+        }
+
+        async fn duration(&self) -> anyhow::Result<Duration> {
+            self.duration.context("No duration")
+        }
+
+        async fn position(&self) -> anyhow::Result<Duration> {
+            Ok(self.position)
+        }
+
+        async fn rate(&self) -> anyhow::Result<f64> {
+            Ok(self.rate)
+        }
+    }
+
+    #[derive(Default)]
+    struct FakePlayerSource {
+        players: Arc<Mutex<Vec<FakePlayer>>>,
+    }
+
+    #[async_trait]
+    impl PlayerSource for FakePlayerSource {
+        async fn get_players(&self) -> anyhow::Result<Vec<Box<dyn MediaPlayer>>> {
+            let players = self.players.lock().unwrap().clone();
+
+            Ok(players
+                .into_iter()
+                .map(|x| Box::new(x) as Box<dyn MediaPlayer>)
+                .collect())
+        }
+
+        async fn events(&self) -> PlayerEvents {
+            PlayerEvents::polling()
+        }
+
+        fn remote_playbacks(&self) -> Arc<RemotePlaybacks> {
+            Arc::default()
+        }
+    }
+
+    /// Knows a single anime and records the scrobbled episodes. The list is
+    /// reported as already up to date, so that no notification is shown.
+    struct FakeAnimeDbClient {
+        confidence: Confidence,
+        scrobbled: Arc<Mutex<Vec<(AnimeId, i32)>>>,
+    }
+
+    #[async_trait]
+    impl AnimeDbClient for FakeAnimeDbClient {
+        async fn get_anime_info(&self, title: &Title) -> anyhow::Result<Option<AnimeInfo>> {
+            if title.title != "Sousou no Frieren" {
+                return Ok(None);
+            }
+
+            Ok(Some(AnimeInfo {
+                id: AnimeId("52991".to_owned()),
+                picture: PictureUrl::default(),
+                website_url: WebsiteUrl::default(),
+                title: title.title.clone(),
+                episode_watched: title.episode_number,
+                total_episodes: 28,
+                confidence: self.confidence,
+            }))
+        }
+
+        async fn explain(&self, _title: &Title) -> anyhow::Result<MatchExplanation> {
+            Ok(MatchExplanation::default())
+        }
+
+        async fn set_title_watched(&self, anime_info: &AnimeInfo) -> anyhow::Result<bool> {
+            self.scrobbled
+                .lock()
+                .unwrap()
+                .push(anime_info.episode_key());
+            Ok(false)
+        }
+
+        fn clear_cached_title(&self, _title: &Title) {}
+    }
+
+    struct TestApp {
+        app: TundraApp,
+        players: Arc<Mutex<Vec<FakePlayer>>>,
+        scrobbled: Arc<Mutex<Vec<(AnimeId, i32)>>>,
+    }
+
+    impl TestApp {
+        fn new(config: Config, confidence: u8, players: Vec<FakePlayer>) -> Self {
+            let player_source = FakePlayerSource::default();
+            *player_source.players.lock().unwrap() = players;
+            let players = player_source.players.clone();
+            let anime_db_client = FakeAnimeDbClient {
+                confidence: Confidence(confidence),
+                scrobbled: Arc::default(),
+            };
+            let scrobbled = anime_db_client.scrobbled.clone();
+            let learned_mappings =
+                LearnedMappings::load_from(std::env::temp_dir().join("tundra-nonexistent.toml"));
+
+            let app = TundraApp::new(
+                Arc::new(RwLock::new(config)),
+                Box::new(player_source),
+                Box::new(anime_db_client),
+                Arc::new(RwLock::new(learned_mappings)),
+            );
+
+            Self {
+                app,
+                players,
+                scrobbled,
+            }
+        }
+
+        fn set_playing(&self, playing: bool) {
+            for player in self.players.lock().unwrap().iter_mut() {
+                player.playing = playing;
+            }
+        }
+
+        fn set_position(&self, position: Duration) {
+            for player in self.players.lock().unwrap().iter_mut() {
+                player.position = position;
+            }
+        }
+
+        fn scrobbled(&self) -> Vec<(AnimeId, i32)> {
+            self.scrobbled.lock().unwrap().clone()
+        }
+    }
+
+    fn frieren_episode(episode_number: i32) -> (AnimeId, i32) {
+        (AnimeId("52991".to_owned()), episode_number)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn scrobbles_once_threshold_is_reached() {
+        let test = TestApp::new(Config::default(), 90, vec![FakePlayer::new("player")]);
+
+        let titles = test.app.try_scrobble().await.unwrap();
+        assert_eq!(titles.len(), 1);
+        assert_eq!(titles[0].title.episode_number, 5);
+        assert!(!titles[0].scrobbled);
+        assert_eq!(titles[0].time_until_scrobble, Some(DURATION / 2));
+
+        time::advance(DURATION / 2 - Duration::from_secs(60)).await;
+        test.set_position(DURATION / 2 - Duration::from_secs(60));
+        let titles = test.app.try_scrobble().await.unwrap();
+        assert!(!titles[0].scrobbled);
+        assert!(test.scrobbled().is_empty());
+
+        time::advance(Duration::from_secs(60)).await;
+        test.set_position(DURATION / 2);
+        let titles = test.app.try_scrobble().await.unwrap();
+        assert!(titles[0].scrobbled);
+        assert_eq!(test.scrobbled(), [frieren_episode(5)]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn does_not_scrobble_after_seeking() {
+        let test = TestApp::new(Config::default(), 90, vec![FakePlayer::new("player")]);
+
+        test.app.try_scrobble().await.unwrap();
+        time::advance(Duration::from_secs(10)).await;
+        test.set_position(DURATION - Duration::from_secs(60));
+        let titles = test.app.try_scrobble().await.unwrap();
+
+        assert!(!titles[0].scrobbled);
+        assert!(test.scrobbled().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn scrobbles_sooner_at_higher_rate() {
+        let mut player = FakePlayer::new("player");
+        player.rate = 2.0;
+        let test = TestApp::new(Config::default(), 90, vec![player]);
+
+        let titles = test.app.try_scrobble().await.unwrap();
+        assert_eq!(titles[0].time_until_scrobble, Some(DURATION / 4));
+
+        time::advance(DURATION / 4).await;
+        test.set_position(DURATION / 2);
+        let titles = test.app.try_scrobble().await.unwrap();
+        assert!(titles[0].scrobbled);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn does_not_count_paused_time() {
+        let test = TestApp::new(Config::default(), 90, vec![FakePlayer::new("player")]);
+
+        test.app.try_scrobble().await.unwrap();
+        time::advance(Duration::from_secs(60)).await;
+        test.set_position(Duration::from_secs(60));
+        test.app.try_scrobble().await.unwrap();
+
+        test.set_playing(false);
+        let titles = test.app.try_scrobble().await.unwrap();
+        assert!(titles.is_empty());
+        time::advance(Duration::from_secs(3600)).await;
+        test.set_playing(true);
+        test.app.try_scrobble().await.unwrap();
+        time::advance(Duration::from_secs(60)).await;
+        test.set_position(Duration::from_secs(120));
+        let titles = test.app.try_scrobble().await.unwrap();
+
+        assert_eq!(
+            titles[0].time_until_scrobble,
+            Some(DURATION / 2 - Duration::from_secs(120))
+        );
+        assert!(!titles[0].scrobbled);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn scrobbles_watched_media_without_duration() {
+        let mut player = FakePlayer::new("player").watched();
+        player.duration = None;
+        let test = TestApp::new(Config::default(), 90, vec![player]);
+
+        let titles = test.app.try_scrobble().await.unwrap();
+
+        assert!(titles[0].scrobbled);
+        assert_eq!(test.scrobbled(), [frieren_episode(5)]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn does_not_scrobble_uncertain_matches() {
+        let test = TestApp::new(
+            Config::default(),
+            50,
+            vec![FakePlayer::new("player").watched()],
+        );
+
+        let titles = test.app.try_scrobble().await.unwrap();
+
+        assert!(titles[0].uncertain);
+        assert!(!titles[0].scrobbled);
+        assert!(test.scrobbled().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn scrobbles_each_episode_once() {
+        let players = vec![
+            FakePlayer::new("player-1").watched(),
+            FakePlayer::new("player-2").watched(),
+        ];
+        let test = TestApp::new(Config::default(), 90, players);
+
+        let titles = test.app.try_scrobble().await.unwrap();
+        assert!(titles.iter().all(|x| x.scrobbled));
+        test.app.try_scrobble().await.unwrap();
+
+        assert_eq!(test.scrobbled(), [frieren_episode(5)]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn skips_ignored_media() {
+        let mut config = Config::default();
+        config.ignore.path_prefixes = vec!["/home/user/Videos".into()];
+        let test = TestApp::new(config, 90, vec![FakePlayer::new("player").watched()]);
+
+        let titles = test.app.try_scrobble().await.unwrap();
+
+        assert!(titles.is_empty());
+        assert!(test.scrobbled().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn skips_ignored_players() {
+        let mut config = Config::default();
+        config.ignore.players = vec!["fake player".to_owned()];
+        let test = TestApp::new(config, 90, vec![FakePlayer::new("player").watched()]);
+
+        let titles = test.app.try_scrobble().await.unwrap();
+
+        assert!(titles.is_empty());
+        assert!(test.scrobbled().is_empty());
+    }
+}
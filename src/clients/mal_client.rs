@@ -13,16 +13,23 @@ use reqwest::StatusCode;
 use serde::Deserialize;
 use tokio::sync::Semaphore;
 use tokio::try_join;
+use url::Url;
 
 use crate::anime_relations::{AnimeDbs, AnimeRelations};
 use crate::clients::oauth2_helper::{
     OAuth2CodeReceiver, OAuth2FlowError, OAuth2Helper, OAuth2Token, PkceCodeChallengeType,
     RefreshToken,
 };
-use crate::clients::{AnimeDbClient, AnimeId, AnimeInfo, PictureUrl, WebsiteUrl};
+use crate::clients::{
+    AnimeDbClient, AnimeId, AnimeInfo, CandidateExplanation, Confidence, MatchExplanation,
+    PictureUrl, SearchResultExplanation, WebsiteUrl,
+};
 use crate::config::Config;
 use crate::constants::{MAL_AUTH_URL, MAL_CLIENT_ID, MAL_TOKEN_URL, MAL_URL, USER_AGENT};
-use crate::title_recognizer::Title;
+use crate::learned_mappings::LearnedMappings;
+use crate::offline_database::OfflineDatabase;
+use crate::title_recognizer::{Title, normalize_key};
+use crate::url_utils::host_matches;
 
 static CLIENT_ID_HEADER: &str = "X-MAL-Client-ID";
 
@@ -60,10 +67,22 @@ enum MediaType {
     Unknown,
 }
 
+#[derive(Clone, Debug, Default, Deserialize)]
+struct AlternativeTitles {
+    #[serde(default)]
+    synonyms: Vec<String>,
+    #[serde(default)]
+    en: String,
+    #[serde(default)]
+    ja: String,
+}
+
 #[derive(Clone, Debug, Deserialize)]
 struct AnimeObject {
     id: i64,
     title: String,
+    #[serde(default)]
+    alternative_titles: AlternativeTitles,
     num_episodes: i32,
     main_picture: PictureObject,
     my_list_status: Option<MyListStatus>,
@@ -73,6 +92,20 @@ struct AnimeObject {
     related_anime: Vec<RelatedAnime>,
 }
 
+impl AnimeObject {
+    fn titles(&self) -> impl Iterator<Item = &str> {
+        let alternative_titles = &self.alternative_titles;
+
+        std::iter::once(self.title.as_str())
+            .chain(alternative_titles.synonyms.iter().map(String::as_str))
+            .chain([
+                alternative_titles.en.as_str(),
+                alternative_titles.ja.as_str(),
+            ])
+            .filter(|x| !x.is_empty())
+    }
+}
+
 #[derive(Clone, Debug, Deserialize)]
 struct PictureObject {
     large: String,
@@ -85,11 +118,34 @@ struct MyListStatus {
     num_episodes_watched: i32,
 }
 
+/// A possible match for a played title.
+#[derive(Debug)]
+struct Candidate {
+    anime_object: AnimeObject,
+    episode_number: i32,
+    relation_rule: Option<String>,
+    confidence: Confidence,
+}
+
+impl From<&Candidate> for CandidateExplanation {
+    fn from(candidate: &Candidate) -> Self {
+        Self {
+            id: AnimeId(candidate.anime_object.id.to_string()),
+            title: candidate.anime_object.title.clone(),
+            episode_number: candidate.episode_number,
+            relation_rule: candidate.relation_rule.clone(),
+            confidence: candidate.confidence,
+        }
+    }
+}
+
 pub struct MalClient {
     config: Arc<RwLock<Config>>,
     client: reqwest::Client,
     anime_relations: Arc<AnimeRelations>,
-    title_cache: HashMap<Title, AnimeInfo>,
+    offline_database: Arc<OfflineDatabase>,
+    learned_mappings: Arc<RwLock<LearnedMappings>>,
+    title_cache: RwLock<HashMap<Title, AnimeInfo>>,
     request_permit: Semaphore,
 }
 
@@ -143,6 +199,13 @@ impl std::error::Error for MalClientError {
 }
 
 const RELATION_TYPE_SEQUEL: &str = "sequel";
+/// Upper bound of sequels followed when looking for the final season.
+const MAX_SEASONS: i32 = 30;
+
+const TITLE_SIMILARITY_WEIGHT: f32 = 0.4;
+const SEASON_WEIGHT: f32 = 0.3;
+const EPISODE_COUNT_WEIGHT: f32 = 0.2;
+const LIST_MEMBERSHIP_WEIGHT: f32 = 0.1;
 
 pub type MalClientResult<T> = Result<T, MalClientError>;
 
@@ -150,6 +213,8 @@ impl MalClient {
     pub fn new(
         config: Arc<RwLock<Config>>,
         anime_relations: Arc<AnimeRelations>,
+        offline_database: Arc<OfflineDatabase>,
+        learned_mappings: Arc<RwLock<LearnedMappings>>,
     ) -> MalClientResult<Self> {
         use reqwest::header;
         let mut headers = header::HeaderMap::new();
@@ -167,13 +232,32 @@ impl MalClient {
             config,
             client,
             anime_relations,
-            title_cache: HashMap::new(),
+            offline_database,
+            learned_mappings,
+            title_cache: RwLock::new(HashMap::new()),
             request_permit: Semaphore::const_new(1),
         })
     }
 
-    pub async fn start_authentication(&self) -> MalClientResult<MalAuthenticator> {
-        MalAuthenticator::start_authentication(self.config.clone()).await
+    /// Parses a MAL anime ID, given either directly or as a URL such as
+    /// `https://myanimelist.net/anime/52991/Sousou_no_Frieren`.
+    pub fn parse_anime_id(input: &str) -> Option<AnimeId> {
+        let input = input.trim();
+        if let Ok(id) = i64::from_str(input) {
+            return Some(AnimeId(id.to_string()));
+        }
+
+        let url = Url::parse(input).ok()?;
+        if !host_matches(url.host_str()?, "myanimelist.net") {
+            return None;
+        }
+        let mut path_segments = url.path_segments()?;
+        if path_segments.next()? != "anime" {
+            return None;
+        }
+        let id = i64::from_str(path_segments.next()?).ok()?;
+
+        Some(AnimeId(id.to_string()))
     }
 
     fn access_token(&self) -> String {
@@ -280,34 +364,203 @@ impl MalClient {
             return Ok(false);
         }
 
-        let new_status = if num_episodes_watched == anime_object.num_episodes {
-            "completed"
-        } else {
-            "watching"
-        };
+        // The number of episodes of movies is sometimes not known until
+        // their release, but they consist of a single episode anyway
+        let completed = num_episodes_watched == anime_object.num_episodes
+            || (anime_object.media_type == MediaType::Movie && anime_object.num_episodes == 0);
+        let new_status = if completed { "completed" } else { "watching" };
         self.set_status(anime_object.id, new_status, num_episodes_watched)
             .await?;
         Ok(true)
     }
 
-    async fn get_anime_object(&self, title: &Title) -> MalClientResult<Option<(AnimeObject, i32)>> {
+    async fn get_anime_object(&self, title: &Title) -> MalClientResult<Option<Candidate>> {
+        let candidates = self.find_candidates(title).await?;
+
+        Ok(self.best_candidate(candidates))
+    }
+
+    /// Picks the most likely candidate. Anime on the user's list are always
+    /// considered (uncertain matches are then shown, but not scrobbled), as
+    /// they used to be the only ones matched. Other anime are only picked if
+    /// their confidence reaches the configured threshold, so that a poor
+    /// match outside the list does not hide a correct one; they are shown as
+    /// being played, but [`AnimeDbClient::set_title_watched`] never adds them
+    /// to the list.
+    fn best_candidate(&self, candidates: Vec<Candidate>) -> Option<Candidate> {
+        let min_confidence = self.min_confidence();
+
+        candidates
+            .into_iter()
+            .filter(|x| Self::is_acceptable(x, min_confidence))
+            .reduce(|best, x| {
+                if x.confidence > best.confidence {
+                    x
+                } else {
+                    best
+                }
+            })
+    }
+
+    fn min_confidence(&self) -> Confidence {
+        Confidence(self.config.read().unwrap().matching.min_confidence)
+    }
+
+    /// Whether the candidate can be picked by [`Self::best_candidate`].
+    fn is_acceptable(candidate: &Candidate, min_confidence: Confidence) -> bool {
+        Self::is_in_my_list(&candidate.anime_object) || candidate.confidence >= min_confidence
+    }
+
+    async fn find_candidates(&self, title: &Title) -> MalClientResult<Vec<Candidate>> {
+        if let Some(anime_object) = self.find_learned_anime(title).await? {
+            let candidate = self.apply_anime_relation(title, anime_object).await?;
+            return Ok(vec![Candidate {
+                confidence: Confidence::CERTAIN,
+                ..candidate
+            }]);
+        }
+
+        let mut candidates = Vec::new();
+
+        if let Some(anime_object) = self.find_anime_locally(title).await? {
+            // The offline database only matches exact titles and synonyms
+            let candidate = self.make_candidate(title, anime_object, 1.0, true).await?;
+            // No need to search MAL if the local match can be used anyway
+            if Self::is_acceptable(&candidate, self.min_confidence()) {
+                return Ok(vec![candidate]);
+            }
+            candidates.push(candidate);
+        }
+
         let (anime_1, anime_2) =
             try_join!(self.find_anime_with_season(title), self.find_anime(title))?;
-        let anime_objects: Vec<AnimeObject> = [anime_1, anime_2].into_iter().flatten().collect();
+        if let Some((anime_object, title_similarity)) = anime_1 {
+            candidates.push(
+                self.make_candidate(title, anime_object, title_similarity, true)
+                    .await?,
+            );
+        }
+        if let Some(anime_object) = anime_2 {
+            let title_similarity = Self::title_similarity(&title.title, &anime_object);
+            let season_matched = title.is_first_season();
+            candidates.push(
+                self.make_candidate(title, anime_object, title_similarity, season_matched)
+                    .await?,
+            );
+        }
 
-        for anime_object in anime_objects {
-            let (anime_object, episode_number) = self
-                .apply_anime_relation(&title.clone(), anime_object)
-                .await?;
-            if Self::is_in_my_list(&anime_object) {
-                return Ok(Some((anime_object, episode_number)));
-            }
+        for candidate in &candidates {
+            debug!(
+                "Candidate for {}: {} (episode {}), confidence {}",
+                title.title,
+                candidate.anime_object.title,
+                candidate.episode_number,
+                candidate.confidence
+            );
+        }
+
+        Ok(candidates)
+    }
+
+    async fn make_candidate(
+        &self,
+        title: &Title,
+        anime_object: AnimeObject,
+        title_similarity: f32,
+        season_matched: bool,
+    ) -> MalClientResult<Candidate> {
+        let mut candidate = self.apply_anime_relation(title, anime_object).await?;
+        candidate.confidence = Self::confidence(
+            title,
+            &candidate.anime_object,
+            candidate.episode_number,
+            title_similarity,
+            season_matched,
+        );
+
+        Ok(candidate)
+    }
+
+    /// Combines the title similarity, whether the season could be found,
+    /// whether the episode number makes sense for the anime (for movies:
+    /// whether it is a single-episode entry) and whether the anime is on the
+    /// user's list into a single confidence score.
+    fn confidence(
+        title: &Title,
+        anime_object: &AnimeObject,
+        episode_number: i32,
+        title_similarity: f32,
+        season_matched: bool,
+    ) -> Confidence {
+        // MAL reports 0 episodes if the number is not known yet
+        let episode_plausible = if title.movie {
+            Self::is_movie_like(anime_object)
+        } else {
+            anime_object.num_episodes == 0
+                || (1..=anime_object.num_episodes).contains(&episode_number)
+        };
+
+        let score = title_similarity * TITLE_SIMILARITY_WEIGHT
+            + f32::from(u8::from(season_matched)) * SEASON_WEIGHT
+            + f32::from(u8::from(episode_plausible)) * EPISODE_COUNT_WEIGHT
+            + f32::from(u8::from(Self::is_in_my_list(anime_object))) * LIST_MEMBERSHIP_WEIGHT;
+
+        Confidence::from_score(score)
+    }
+
+    /// Returns the similarity (between 0 and 1) of the query to the closest
+    /// title of the anime, including its alternative titles.
+    fn title_similarity(query: &str, anime_object: &AnimeObject) -> f32 {
+        let query = normalize_key(query);
+
+        anime_object
+            .titles()
+            .map(|x| {
+                let x = normalize_key(x);
+                let length = query.chars().count().max(x.chars().count());
+                if length == 0 {
+                    return 0.0;
+                }
+                let dist = edit_distance::edit_distance(&query, &x);
+
+                1.0 - dist as f32 / length as f32
+            })
+            .fold(0.0, f32::max)
+    }
+
+    async fn find_learned_anime(&self, title: &Title) -> MalClientResult<Option<AnimeObject>> {
+        let anime_id = self
+            .learned_mappings
+            .read()
+            .unwrap()
+            .get(title)
+            .and_then(|x| i64::from_str(&x.0).ok());
+
+        if let Some(anime_id) = anime_id {
+            debug!(
+                "Using learned match for {} season {}: {anime_id}",
+                title.title, title.season_number
+            );
+            Ok(Some(self.get_by_id(anime_id).await?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    async fn find_anime_locally(&self, title: &Title) -> MalClientResult<Option<AnimeObject>> {
+        if let Some(local_match) = self.offline_database.resolve(title) {
+            debug!(
+                "Resolved {} season {} locally as {} ({} episodes)",
+                title.title, title.season_number, local_match.title, local_match.episodes
+            );
+            Ok(Some(self.get_by_id(local_match.mal_id).await?))
+        } else {
+            Ok(None)
         }
-        Ok(None)
     }
 
     async fn find_anime(&self, title: &Title) -> MalClientResult<Option<AnimeObject>> {
-        let results = self.search(&title.title).await?;
+        let results = self.search(&title.search_query()).await?;
 
         let first_result = results.data.into_iter().next();
         if let Some(search_response_object) = first_result {
@@ -317,8 +570,13 @@ impl MalClient {
         }
     }
 
-    async fn find_anime_with_season(&self, title: &Title) -> MalClientResult<Option<AnimeObject>> {
-        let results = self.search(&title.title).await?;
+    /// Returns the given season of the most relevant search result, along
+    /// with the title similarity of that search result.
+    async fn find_anime_with_season(
+        &self,
+        title: &Title,
+    ) -> MalClientResult<Option<(AnimeObject, f32)>> {
+        let results = self.search(&title.search_query()).await?;
         let mut data = results.data;
         data.sort_by(|a, b| {
             let a_rel = Self::search_relevance(&title.title, &a.node);
@@ -326,13 +584,25 @@ impl MalClient {
             b_rel.partial_cmp(&a_rel).unwrap_or(Ordering::Equal)
         });
 
+        if title.movie {
+            // Movies are not a part of the sequel chain the same way seasons
+            // are; just pick the most relevant single-episode entry
+            let movie = data.into_iter().map(|x| x.node).find(Self::is_movie_like);
+            return Ok(movie.map(|x| {
+                let title_similarity = Self::title_similarity(&title.title, &x);
+                (x, title_similarity)
+            }));
+        }
+
         let first_result = data.into_iter().next();
         if let Some(search_response_object) = first_result {
             let anime_object = search_response_object.node;
+            let title_similarity = Self::title_similarity(&title.title, &anime_object);
 
             Ok(self
-                .get_nth_season(anime_object.id, title.season_number)
-                .await?)
+                .get_nth_season(anime_object.id, title)
+                .await?
+                .map(|x| (x, title_similarity)))
         } else {
             Ok(None)
         }
@@ -347,80 +617,152 @@ impl MalClient {
         edit_distance_relevance * 0.5 + popularity_relevance * 0.5
     }
 
+    /// Walks the sequels of given anime to find the season (and its part)
+    /// the title refers to.
     async fn get_nth_season(
         &self,
         anime_id: i64,
-        season_number: i32,
+        title: &Title,
+    ) -> MalClientResult<Option<AnimeObject>> {
+        let Some(mut anime_object) = self.find_season(anime_id, title).await? else {
+            return Ok(None);
+        };
+
+        for _ in 1..title.part_number {
+            let Some(sequel_id) = Self::sequel_id(&anime_object) else {
+                return Ok(None);
+            };
+            anime_object = self.get_by_id(sequel_id).await?;
+        }
+
+        Ok(Some(anime_object))
+    }
+
+    /// Finds the first entry of given season. The final season is the first
+    /// season having "final" in one of its titles or, failing that, the last
+    /// season that has been aired.
+    async fn find_season(
+        &self,
+        anime_id: i64,
+        title: &Title,
     ) -> MalClientResult<Option<AnimeObject>> {
         let mut current_season = 0;
         let mut current_id = anime_id;
+        let mut last_season = None;
 
-        while current_season <= season_number {
+        while title.final_season || current_season <= title.season_number {
             let anime_object = self.get_by_id(current_id).await?;
             if anime_object.media_type != MediaType::Ova
                 && anime_object.media_type != MediaType::Music
                 && anime_object.media_type != MediaType::Special
             {
                 current_season += 1;
-            }
-            if current_season == season_number {
-                return Ok(Some(anime_object));
-            }
 
-            let sequel = anime_object
-                .related_anime
-                .iter()
-                .find(|x| x.relation_type == RELATION_TYPE_SEQUEL);
+                if title.final_season {
+                    if anime_object
+                        .titles()
+                        .any(|x| x.to_lowercase().contains("final"))
+                    {
+                        return Ok(Some(anime_object));
+                    }
+                    if current_season > MAX_SEASONS {
+                        return Ok(None);
+                    }
+                    last_season = Some(anime_object.clone());
+                } else if current_season == title.season_number {
+                    return Ok(Some(anime_object));
+                }
+            }
 
-            if let Some(sequel) = sequel {
-                current_id = sequel.node.id;
+            if let Some(sequel_id) = Self::sequel_id(&anime_object) {
+                current_id = sequel_id;
             } else {
-                return Ok(None);
+                return Ok(last_season);
             }
         }
 
         Ok(None)
     }
 
+    fn sequel_id(anime_object: &AnimeObject) -> Option<i64> {
+        anime_object
+            .related_anime
+            .iter()
+            .find(|x| x.relation_type == RELATION_TYPE_SEQUEL)
+            .map(|x| x.node.id)
+    }
+
+    fn is_movie_like(anime_object: &AnimeObject) -> bool {
+        matches!(
+            anime_object.media_type,
+            MediaType::Movie | MediaType::Special | MediaType::Ova | MediaType::Ona
+        ) && anime_object.num_episodes <= 1
+    }
+
     fn is_in_my_list(anime_object: &AnimeObject) -> bool {
         anime_object.my_list_status.is_some()
     }
 
+    /// Converts the episode number using the anime-relations rules (e.g.
+    /// episode 13 of a show whose second cour is listed separately on MAL).
+    /// The confidence of the returned candidate is not computed yet.
     async fn apply_anime_relation(
         &self,
         title: &Title,
         anime_object: AnimeObject,
-    ) -> MalClientResult<(AnimeObject, i32)> {
+    ) -> MalClientResult<Candidate> {
+        if title.movie && Self::is_movie_like(&anime_object) {
+            // Whatever number was found in the filename, a movie has just
+            // one episode
+            return Ok(Candidate {
+                anime_object,
+                episode_number: 1,
+                relation_rule: None,
+                confidence: Confidence::default(),
+            });
+        }
+
         debug!("Applying anime relation for {}", title.title);
         let relation_rule = self
             .anime_relations
             .get_rule(&AnimeDbs::Mal, anime_object.id);
 
         for rule in relation_rule {
-            debug!("Applying rule {rule:?}");
+            debug!("Applying rule {rule}");
 
             let (new_id, new_ep) =
                 rule.convert_episode_number(&AnimeDbs::Mal, anime_object.id, title.episode_number);
 
             if (anime_object.id, title.episode_number) != (new_id, new_ep) {
                 let new_anime_object = self.get_by_id(new_id).await?;
-                return Ok((new_anime_object, new_ep));
+                return Ok(Candidate {
+                    anime_object: new_anime_object,
+                    episode_number: new_ep,
+                    relation_rule: Some(rule.to_string()),
+                    confidence: Confidence::default(),
+                });
             }
         }
 
-        Ok((anime_object, title.episode_number))
+        Ok(Candidate {
+            anime_object,
+            episode_number: title.episode_number,
+            relation_rule: None,
+            confidence: Confidence::default(),
+        })
     }
 }
 
 #[async_trait]
 impl AnimeDbClient for MalClient {
-    async fn get_anime_info(&mut self, title: &Title) -> anyhow::Result<Option<AnimeInfo>> {
-        if self.title_cache.contains_key(title) {
-            return Ok(Some(self.title_cache[title].clone()));
+    async fn get_anime_info(&self, title: &Title) -> anyhow::Result<Option<AnimeInfo>> {
+        if let Some(anime_info) = self.title_cache.read().unwrap().get(title) {
+            return Ok(Some(anime_info.clone()));
         }
 
-        let anime_object = self.get_anime_object(title).await?;
-        let anime_info = anime_object.map(|(anime_object, episode_number)| {
+        let candidate = self.get_anime_object(title).await?;
+        let anime_info = candidate.map(|candidate| {
+            let anime_object = candidate.anime_object;
             let id = AnimeId(anime_object.id.to_string());
             let website_url = WebsiteUrl(format!("https://myanimelist.net/anime/{id}"));
             let picture_url = PictureUrl(anime_object.main_picture.large);
@@ -429,27 +771,54 @@ impl AnimeDbClient for MalClient {
                 title: anime_object.title,
                 website_url,
                 picture: picture_url,
-                episode_watched: episode_number,
+                episode_watched: candidate.episode_number,
                 total_episodes: anime_object.num_episodes,
+                confidence: candidate.confidence,
             }
         });
 
         if let Some(anime_info_val) = &anime_info {
             self.title_cache
+                .write()
+                .unwrap()
                 .insert(title.clone(), anime_info_val.clone());
         }
 
         Ok(anime_info)
     }
 
-    async fn set_title_watched(&mut self, anime_info: &AnimeInfo) -> anyhow::Result<bool> {
+    async fn explain(&self, title: &Title) -> anyhow::Result<MatchExplanation> {
+        let search_results = self
+            .search(&title.search_query())
+            .await?
+            .data
+            .iter()
+            .map(|x| SearchResultExplanation {
+                id: AnimeId(x.node.id.to_string()),
+                title: x.node.title.clone(),
+                relevance: Self::search_relevance(&title.title, &x.node),
+                title_similarity: Self::title_similarity(&title.title, &x.node),
+            })
+            .collect();
+        let candidates = self.find_candidates(title).await?;
+
+        Ok(MatchExplanation {
+            search_results,
+            candidates: candidates.iter().map(CandidateExplanation::from).collect(),
+            result: self
+                .best_candidate(candidates)
+                .as_ref()
+                .map(CandidateExplanation::from),
+        })
+    }
+
+    async fn set_title_watched(&self, anime_info: &AnimeInfo) -> anyhow::Result<bool> {
         let anime_object = self.get_by_id(i64::from_str(&anime_info.id.0)?).await?;
 
-        let episodes_watched = anime_object
-            .my_list_status
-            .as_ref()
-            .unwrap()
-            .num_episodes_watched;
+        let Some(my_list_status) = &anime_object.my_list_status else {
+            return Ok(false);
+        };
+        let episodes_watched = my_list_status.num_episodes_watched;
         let episode_number = anime_info.episode_watched;
         if episodes_watched < episode_number {
             self.set_episode_number(&anime_object, episode_number)
@@ -459,6 +828,13 @@ impl AnimeDbClient for MalClient {
             Ok(false)
         }
     }
+
+    fn clear_cached_title(&self, title: &Title) {
+        self.title_cache
+            .write()
+            .unwrap()
+            .retain(|x, _| !LearnedMappings::same_key(x, title));
+    }
 }
 
 #[derive(Debug)]
@@ -522,3 +898,80 @@ impl MalAuthenticator {
             .set_pkce_code_challenge_type(PkceCodeChallengeType::Plain)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn candidate(confidence: u8, in_my_list: bool) -> Candidate {
+        let my_list_status = in_my_list.then(|| json!({"num_episodes_watched": 3}));
+        let anime_object = serde_json::from_value(json!({
+            "id": 52991,
+            "title": "Sousou no Frieren",
+            "num_episodes": 28,
+            "main_picture": {"large": "https://cdn.myanimelist.net/images/anime/1015/138006l.jpg"},
+            "my_list_status": my_list_status,
+            "media_type": "tv",
+            "popularity": 200,
+        }))
+        .unwrap();
+
+        Candidate {
+            anime_object,
+            episode_number: 4,
+            relation_rule: None,
+            confidence: Confidence(confidence),
+        }
+    }
+
+    #[test]
+    fn parses_anime_ids() {
+        let id = Some(AnimeId("52991".to_owned()));
+
+        assert_eq!(MalClient::parse_anime_id(" 52991 "), id);
+        assert_eq!(
+            MalClient::parse_anime_id("https://myanimelist.net/anime/52991/Sousou_no_Frieren"),
+            id
+        );
+        assert_eq!(
+            MalClient::parse_anime_id("https://www.myanimelist.net/anime/52991"),
+            id
+        );
+        assert_eq!(
+            MalClient::parse_anime_id("https://myanimelist.net/manga/126287"),
+            None
+        );
+        assert_eq!(
+            MalClient::parse_anime_id("https://evilmyanimelist.net/anime/52991"),
+            None
+        );
+        assert_eq!(
+            MalClient::parse_anime_id("https://myanimelist.net.example.com/anime/52991"),
+            None
+        );
+    }
+
+    #[test]
+    fn confident_local_match_outside_list_needs_no_search() {
+        let min_confidence = Confidence(75);
+
+        assert!(MalClient::is_acceptable(
+            &candidate(90, false),
+            min_confidence
+        ));
+        assert!(MalClient::is_acceptable(
+            &candidate(75, false),
+            min_confidence
+        ));
+        assert!(MalClient::is_acceptable(
+            &candidate(40, true),
+            min_confidence
+        ));
+        assert!(!MalClient::is_acceptable(
+            &candidate(74, false),
+            min_confidence
+        ));
+    }
+}
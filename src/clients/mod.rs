@@ -33,7 +33,28 @@ impl Display for PictureUrl {
         write!(f, "{}", self.0)
     }
 }
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+
+/// How sure we are that an anime is the one being played, in percent.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Confidence(pub u8);
+
+impl Confidence {
+    /// Used for matches confirmed by the user.
+    pub const CERTAIN: Self = Self(100);
+
+    /// Converts a score between 0 and 1 to a confidence.
+    pub fn from_score(score: f32) -> Self {
+        Self((score.clamp(0.0, 1.0) * 100.0).round() as u8)
+    }
+}
+
+impl Display for Confidence {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}%", self.0)
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct AnimeInfo {
     pub id: AnimeId,
     pub picture: PictureUrl,
@@ -41,11 +62,57 @@ pub struct AnimeInfo {
     pub title: String,
     pub episode_watched: i32,
     pub total_episodes: i32,
+    pub confidence: Confidence,
+}
+
+impl AnimeInfo {
+    /// Identifies the episode regardless of how it has been matched, so that
+    /// it is not scrobbled again when e.g. the match confidence changes.
+    pub fn episode_key(&self) -> (AnimeId, i32) {
+        (self.id.clone(), self.episode_watched)
+    }
+}
+
+/// A search result, as shown by [`AnimeDbClient::explain`].
+#[derive(Clone, Debug)]
+pub struct SearchResultExplanation {
+    pub id: AnimeId,
+    pub title: String,
+    pub relevance: f32,
+    pub title_similarity: f32,
+}
+
+/// A candidate match, as shown by [`AnimeDbClient::explain`].
+#[derive(Clone, Debug)]
+pub struct CandidateExplanation {
+    pub id: AnimeId,
+    pub title: String,
+    pub episode_number: i32,
+    /// The anime-relations rule used to get the episode number, if any.
+    pub relation_rule: Option<String>,
+    pub confidence: Confidence,
+}
+
+/// Everything that was considered when matching a title.
+#[derive(Clone, Debug, Default)]
+pub struct MatchExplanation {
+    pub search_results: Vec<SearchResultExplanation>,
+    pub candidates: Vec<CandidateExplanation>,
+    pub result: Option<CandidateExplanation>,
 }
 
 #[async_trait]
-pub trait AnimeDbClient {
-    async fn get_anime_info(&mut self, title: &Title) -> anyhow::Result<Option<AnimeInfo>>;
+pub trait AnimeDbClient: Send + Sync {
+    async fn get_anime_info(&self, title: &Title) -> anyhow::Result<Option<AnimeInfo>>;
+
+    /// Matches given title the same way as [`Self::get_anime_info`] does,
+    /// but returns all the search results and candidates considered along
+    /// the way. Nothing is cached nor updated.
+    async fn explain(&self, title: &Title) -> anyhow::Result<MatchExplanation>;
+
+    async fn set_title_watched(&self, anime_info: &AnimeInfo) -> anyhow::Result<bool>;
 
-    async fn set_title_watched(&mut self, anime_info: &AnimeInfo) -> anyhow::Result<bool>;
+    /// Forgets cached matches of given title (of any episode), so that it is
+    /// matched again next time.
+    fn clear_cached_title(&self, title: &Title);
 }
@@ -1,13 +1,37 @@
 use std::fs;
-use std::path::PathBuf;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use directories::ProjectDirs;
 use log::warn;
 use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::title_recognizer::PlayedMedia;
+use crate::url_utils::host_matches;
 
 #[derive(Debug, Deserialize, Default, Serialize)]
 pub struct Config {
     pub mal: MALConfig,
+    #[serde(default)]
+    pub offline_database: OfflineDatabaseConfig,
+    #[serde(default)]
+    pub matching: MatchingConfig,
+    #[serde(default)]
+    pub ignore: IgnoreConfig,
+    #[serde(default)]
+    pub recognition: RecognitionConfig,
+    #[serde(default)]
+    pub scrobble: ScrobbleConfig,
+    #[serde(default)]
+    pub mpv: MpvConfig,
+    #[serde(default)]
+    pub vlc: VlcConfig,
+    #[serde(default)]
+    pub kodi: KodiConfig,
+    #[serde(default)]
+    pub webhook: WebhookConfig,
 }
 
 #[derive(Debug, Deserialize, Default, Serialize)]
@@ -16,15 +40,307 @@ pub struct MALConfig {
     pub refresh_token: String,
 }
 
+#[derive(Debug, Deserialize, Default, Serialize)]
+pub struct OfflineDatabaseConfig {
+    /// Path to an anime-offline-database JSON dump; if not set,
+    /// `anime-offline-database.json` in the data directory is used if it
+    /// exists.
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MatchingConfig {
+    /// Matches with lower confidence (in percent) are considered uncertain
+    /// and are not scrobbled automatically.
+    pub min_confidence: u8,
+}
+
+impl Default for MatchingConfig {
+    fn default() -> Self {
+        Self { min_confidence: 75 }
+    }
+}
+
+const DEFAULT_WATCHED_FRACTION: f32 = 0.5;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ScrobbleConfig {
+    /// Part of the episode (between 0 and 1) that has to be actually watched,
+    /// not skipped over, before it is scrobbled.
+    pub watched_fraction: f32,
+    /// Media shorter than this (in seconds), such as openings or trailers,
+    /// are recognized, but never scrobbled; 0 to disable. Unlike
+    /// `ignore.min_duration`, this still shows what is being played.
+    pub min_duration: u64,
+    /// If set, episodes are scrobbled once they have been watched up to this
+    /// many minutes before the end, instead of using `watched_fraction`.
+    /// Useful for shows with long credits. Episodes not longer than that
+    /// still use `watched_fraction`.
+    pub remaining_minutes: Option<u64>,
+}
+
+impl ScrobbleConfig {
+    /// Returns whether an episode of given duration should be scrobbled after
+    /// it has been watched for `watched`.
+    pub fn should_scrobble(&self, duration: Duration, watched: Duration) -> bool {
+        self.remaining_watch_time(duration, watched)
+            .is_some_and(|x| x.is_zero())
+    }
+
+    /// Returns how much more of an episode of given duration has to be
+    /// watched before it is scrobbled, or `None` if it is never scrobbled.
+    pub fn remaining_watch_time(&self, duration: Duration, watched: Duration) -> Option<Duration> {
+        if duration.is_zero() || duration < Duration::from_secs(self.min_duration) {
+            return None;
+        }
+
+        Some(self.required_watch_time(duration).saturating_sub(watched))
+    }
+
+    fn required_watch_time(&self, duration: Duration) -> Duration {
+        let remaining = self
+            .remaining_minutes
+            .map(|x| Duration::from_secs(x.saturating_mul(60)));
+
+        match remaining {
+            Some(remaining) if duration > remaining => duration - remaining,
+            _ => duration.mul_f32(self.watched_fraction()),
+        }
+    }
+
+    fn watched_fraction(&self) -> f32 {
+        if self.watched_fraction.is_finite() {
+            self.watched_fraction.clamp(0.0, 1.0)
+        } else {
+            DEFAULT_WATCHED_FRACTION
+        }
+    }
+}
+
+impl Default for ScrobbleConfig {
+    fn default() -> Self {
+        Self {
+            watched_fraction: DEFAULT_WATCHED_FRACTION,
+            min_duration: 0,
+            remaining_minutes: None,
+        }
+    }
+}
+
+/// mpv instances to control over the JSON IPC (`--input-ipc-server`), for
+/// when the mpv-mpris plugin is not installed. If both are used, the episode
+/// is still scrobbled only once.
+#[derive(Clone, Debug, Deserialize, Default, Serialize)]
+#[serde(default)]
+pub struct MpvConfig {
+    /// Paths of the IPC sockets.
+    pub sockets: Vec<PathBuf>,
+    /// All the sockets inside these directories are used as well, which is
+    /// useful when every mpv instance creates its own socket.
+    pub socket_dirs: Vec<PathBuf>,
+}
+
+impl MpvConfig {
+    pub fn is_enabled(&self) -> bool {
+        !self.sockets.is_empty() || !self.socket_dirs.is_empty()
+    }
+}
+
+/// VLC to control over its Lua HTTP interface (`--extraintf http`), for when
+/// its MPRIS support is broken.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct VlcConfig {
+    /// Address of the HTTP interface.
+    pub url: String,
+    /// Password of the HTTP interface (`--http-password`); the interface is
+    /// not used if not set.
+    pub password: Option<String>,
+}
+
+impl VlcConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.password.is_some()
+    }
+}
+
+impl Default for VlcConfig {
+    fn default() -> Self {
+        Self {
+            url: "http://localhost:8080".to_owned(),
+            password: None,
+        }
+    }
+}
+
+/// Kodi to control over its JSON-RPC interface ("Allow remote control via
+/// HTTP" in the Kodi settings).
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct KodiConfig {
+    /// Address of the web server, e.g. `http://192.168.1.10:8080`; Kodi is
+    /// not used if not set.
+    pub url: Option<String>,
+    pub username: String,
+    pub password: Option<String>,
+}
+
+impl KodiConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.url.is_some()
+    }
+}
+
+impl Default for KodiConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            username: "kodi".to_owned(),
+            password: None,
+        }
+    }
+}
+
+/// Local HTTP server receiving the webhooks of media servers, so that the
+/// episodes watched through them (e.g. on a TV) are scrobbled as well.
+#[derive(Clone, Debug, Deserialize, Default, Serialize)]
+#[serde(default)]
+pub struct WebhookConfig {
+    /// Address to listen on, e.g. `127.0.0.1:8097`, or `0.0.0.0:8097` if the
+    /// media server runs on another machine; the server is not started if
+    /// not set.
+    pub address: Option<SocketAddr>,
+    /// If set, the webhook URLs need to contain it as the `token` query
+    /// parameter, e.g. `http://192.168.1.2:8097/jellyfin?token=...`.
+    pub token: Option<String>,
+    /// If not empty, only the playbacks of these Plex accounts are
+    /// scrobbled.
+    pub plex_accounts: Vec<String>,
+}
+
+impl WebhookConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.address.is_some()
+    }
+}
+
+/// Rules for media that should not be recognized nor scrobbled at all.
+#[derive(Clone, Debug, Deserialize, Default, Serialize)]
+#[serde(default)]
+pub struct IgnoreConfig {
+    /// Player identities (e.g. "Spotify"), compared case-insensitively.
+    pub players: Vec<String>,
+    /// Player desktop entries (e.g. "org.gnome.Music").
+    pub desktop_entries: Vec<String>,
+    /// Local files inside these directories are ignored.
+    pub path_prefixes: Vec<PathBuf>,
+    /// Media from these hosts (and their subdomains) are ignored.
+    pub url_hosts: Vec<String>,
+    /// Media shorter than this (in seconds) are ignored; 0 to disable.
+    pub min_duration: u64,
+    /// If not empty, only local files inside these directories are
+    /// recognized.
+    pub library_folders: Vec<PathBuf>,
+}
+
+impl IgnoreConfig {
+    pub fn is_player_ignored(&self, identity: &str, desktop_entry: Option<&str>) -> bool {
+        self.players
+            .iter()
+            .any(|x| x.eq_ignore_ascii_case(identity))
+            || desktop_entry.is_some_and(|desktop_entry| {
+                self.desktop_entries
+                    .iter()
+                    .any(|x| x.eq_ignore_ascii_case(desktop_entry))
+            })
+    }
+
+    pub fn is_media_ignored(&self, media: &PlayedMedia) -> bool {
+        let url = media.url.as_deref().and_then(|x| Url::parse(x).ok());
+        let path = url.as_ref().and_then(|x| x.to_file_path().ok());
+
+        if let Some(path) = &path
+            && self.path_prefixes.iter().any(|x| path.starts_with(x))
+        {
+            return true;
+        }
+
+        if let Some(host) = url.as_ref().and_then(Url::host_str)
+            && self.url_hosts.iter().any(|x| host_matches(host, x))
+        {
+            return true;
+        }
+
+        if self.min_duration > 0
+            && media
+                .duration
+                .is_some_and(|x| x < Duration::from_secs(self.min_duration))
+        {
+            return true;
+        }
+
+        !self.library_folders.is_empty() && !path.is_some_and(|x| self.is_in_library(&x))
+    }
+
+    fn is_in_library(&self, path: &Path) -> bool {
+        self.library_folders.iter().any(|x| path.starts_with(x))
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RecognitionConfig {
+    /// Names of the recognizers to use, in the order they are run.
+    pub recognizers: Vec<String>,
+    pub strategy: RecognitionStrategy,
+}
+
+impl Default for RecognitionConfig {
+    fn default() -> Self {
+        Self {
+            recognizers: [
+                "metadata",
+                "streaming-site",
+                "ani-cli",
+                "matroska",
+                "anitomy",
+            ]
+            .into_iter()
+            .map(String::from)
+            .collect(),
+            strategy: RecognitionStrategy::default(),
+        }
+    }
+}
+
+/// How to pick the title when several recognizers match.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RecognitionStrategy {
+    /// Use the first recognizer that has matched.
+    #[default]
+    FirstMatch,
+    /// Run all the recognizers and use the most reliable match.
+    BestConfidence,
+}
+
 impl Config {
     fn new() -> Self {
         Default::default()
     }
 
+    fn project_dirs() -> ProjectDirs {
+        ProjectDirs::from("com", "m4tx", "Tundra").expect("config directory could not be obtained")
+    }
+
     fn config_path() -> PathBuf {
-        let project_dirs = ProjectDirs::from("com", "m4tx", "Tundra")
-            .expect("config directory could not be obtained");
-        project_dirs.config_dir().to_owned()
+        Self::project_dirs().config_dir().to_owned()
+    }
+
+    pub fn data_path() -> PathBuf {
+        Self::project_dirs().data_dir().to_owned()
     }
 
     pub fn load() -> Self {
@@ -47,7 +363,101 @@ impl Config {
         fs::write(config_file, toml).expect("Could not write config to file");
     }
 
+    pub fn offline_database_path(&self) -> PathBuf {
+        self.offline_database
+            .path
+            .clone()
+            .unwrap_or_else(|| Self::data_path().join("anime-offline-database.json"))
+    }
+
     pub fn is_mal_authenticated(&self) -> bool {
         !self.mal.access_token.is_empty() && !self.mal.refresh_token.is_empty()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn minutes(minutes: u64) -> Duration {
+        Duration::from_secs(minutes * 60)
+    }
+
+    #[test]
+    fn scrobbles_after_watched_fraction() {
+        let config = ScrobbleConfig::default();
+
+        assert_eq!(
+            config.remaining_watch_time(minutes(24), minutes(2)),
+            Some(minutes(10))
+        );
+        assert!(!config.should_scrobble(minutes(24), minutes(11)));
+        assert!(config.should_scrobble(minutes(24), minutes(12)));
+    }
+
+    #[test]
+    fn does_not_scrobble_short_media() {
+        let config = ScrobbleConfig {
+            min_duration: 5 * 60,
+            ..Default::default()
+        };
+
+        assert_eq!(config.remaining_watch_time(minutes(3), minutes(3)), None);
+        assert_eq!(
+            config.remaining_watch_time(Duration::ZERO, Duration::ZERO),
+            None
+        );
+        assert!(config.should_scrobble(minutes(6), minutes(3)));
+    }
+
+    #[test]
+    fn scrobbles_when_minutes_remain() {
+        let config = ScrobbleConfig {
+            remaining_minutes: Some(3),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            config.remaining_watch_time(minutes(24), minutes(20)),
+            Some(minutes(1))
+        );
+        assert!(config.should_scrobble(minutes(24), minutes(21)));
+    }
+
+    #[test]
+    fn uses_watched_fraction_when_media_is_not_longer_than_remaining_minutes() {
+        let config = ScrobbleConfig {
+            remaining_minutes: Some(3),
+            ..Default::default()
+        };
+
+        for duration in [minutes(2), minutes(3)] {
+            assert_eq!(
+                config.remaining_watch_time(duration, Duration::ZERO),
+                Some(duration / 2),
+                "{duration:?}"
+            );
+            assert!(!config.should_scrobble(duration, Duration::ZERO));
+        }
+    }
+
+    #[test]
+    fn uses_default_fraction_when_watched_fraction_is_not_finite() {
+        for watched_fraction in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+            let config = ScrobbleConfig {
+                watched_fraction,
+                ..Default::default()
+            };
+
+            assert_eq!(
+                config.remaining_watch_time(minutes(24), Duration::ZERO),
+                Some(minutes(12)),
+                "{watched_fraction}"
+            );
+        }
+
+        let config: ScrobbleConfig = toml::from_str("watched_fraction = nan").unwrap();
+        assert!(!config.should_scrobble(minutes(24), Duration::ZERO));
+        assert!(config.should_scrobble(minutes(24), minutes(12)));
+    }
+}
@@ -23,3 +23,10 @@ pub const REFRESH_INTERVAL: Duration = if cfg!(debug_assertions) {
 } else {
     Duration::from_secs(20)
 };
+// When nothing is left to scrobble and player events are received, only check
+// players every IDLE_REFRESH_INTERVAL seconds in case a player has missed
+// emitting an event
+pub const IDLE_REFRESH_INTERVAL: Duration = Duration::from_secs(120);
+
+// A player that does not answer all queries within PLAYER_TIMEOUT is skipped
+pub const PLAYER_TIMEOUT: Duration = Duration::from_secs(5);
@@ -0,0 +1,89 @@
+use gettextrs::gettext;
+use gtk::glib::clone;
+use gtk::{Orientation, glib};
+use libadwaita::prelude::*;
+use libadwaita::{ApplicationWindow, HeaderBar, Window};
+
+pub struct CorrectMatchDialog {
+    window: Window,
+    entry: gtk::Entry,
+    save_button: gtk::Button,
+}
+
+const DEFAULT_WIDTH: i32 = 400;
+
+impl CorrectMatchDialog {
+    pub fn new(
+        application: &gtk::Application,
+        application_window: &ApplicationWindow,
+        title: &str,
+    ) -> Self {
+        let label = gtk::Label::new(Some(
+            &gettext("Enter the MyAnimeList URL or ID of the anime that is being played as “{}”.")
+                .replace("{}", title),
+        ));
+        label.set_wrap(true);
+        label.set_xalign(0.0);
+
+        let entry = gtk::Entry::new();
+        entry.set_placeholder_text(Some("https://myanimelist.net/anime/…"));
+
+        let save_button = gtk::Button::with_mnemonic(&gettext("_Save"));
+        save_button.style_context().add_class("suggested-action");
+        save_button.set_halign(gtk::Align::End);
+
+        let content = gtk::Box::new(Orientation::Vertical, 10);
+        content.set_margin_start(10);
+        content.set_margin_end(10);
+        content.set_margin_top(10);
+        content.set_margin_bottom(10);
+        content.append(&label);
+        content.append(&entry);
+        content.append(&save_button);
+
+        let header_bar = HeaderBar::builder()
+            .title_widget(&libadwaita::WindowTitle::new(&gettext("Wrong Match"), ""))
+            .build();
+
+        let window_content = gtk::Box::new(Orientation::Vertical, 0);
+        window_content.append(&header_bar);
+        window_content.append(&content);
+
+        let window = Window::builder()
+            .application(application)
+            .default_width(DEFAULT_WIDTH)
+            .content(&window_content)
+            .modal(true)
+            .transient_for(application_window)
+            .build();
+
+        entry.connect_activate(clone!(
+            #[weak]
+            save_button,
+            move |_| {
+                save_button.emit_clicked();
+            }
+        ));
+
+        Self {
+            window,
+            entry,
+            save_button,
+        }
+    }
+
+    /// Calls `f` with the text entered by the user and closes the dialog.
+    pub fn connect_save<F: Fn(String) + 'static>(&self, f: F) {
+        let window = self.window.clone();
+        let entry = self.entry.clone();
+        self.save_button.connect_clicked(move |_| {
+            f(entry.text().to_string());
+            window.close();
+        });
+    }
+
+    pub fn show(&self) {
+        self.window.show();
+        self.entry.grab_focus();
+    }
+}
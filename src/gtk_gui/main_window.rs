@@ -8,8 +8,8 @@ use gtk::{
 use libadwaita::prelude::*;
 use libadwaita::{ApplicationWindow, HeaderBar};
 
-use crate::clients::WebsiteUrl;
 use crate::gtk_gui::login_page::LoginPage;
+use crate::gtk_gui::played_title_view::PlayedTitleInfo;
 use crate::gtk_gui::scrobble_page::ScrobblePage;
 
 pub struct MainWindow {
@@ -170,6 +170,14 @@ impl MainWindow {
         });
     }
 
+    pub fn connect_confirm_match<F: Fn(usize) + 'static>(&self, f: F) {
+        self.scrobble_page.connect_confirm_match(f);
+    }
+
+    pub fn connect_correct_match<F: Fn(usize) + 'static>(&self, f: F) {
+        self.scrobble_page.connect_correct_match(f);
+    }
+
     pub fn connect_enable_switch<F: Fn(bool) + 'static>(&self, f: F) {
         self.enable_switch.connect_state_set(move |_, state| {
             f(state);
@@ -212,13 +220,14 @@ impl MainWindow {
         self.info_bar.set_revealed(false);
     }
 
+    pub fn set_played_title_count(&self, count: usize) {
+        self.scrobble_page.set_played_title_count(count);
+    }
+
     pub fn set_anime_info(
         &self,
-        title: &str,
-        episode: &str,
-        player_name: &str,
-        status: &str,
-        website_url: &WebsiteUrl,
+        index: usize,
+        info: &PlayedTitleInfo,
         picture: Option<gtk::glib::Bytes>,
     ) {
         let picture_texture = picture.map(|bytes| {
@@ -228,14 +237,8 @@ impl MainWindow {
             gdk::Texture::for_pixbuf(&pixbuf)
         });
 
-        self.scrobble_page.set_anime_info(
-            title,
-            episode,
-            player_name,
-            status,
-            &website_url.0,
-            picture_texture,
-        );
+        self.scrobble_page
+            .set_anime_info(index, info, picture_texture);
     }
 
     pub fn set_anime_info_none(&self) {
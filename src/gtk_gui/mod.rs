@@ -6,25 +6,29 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, RwLock};
 
 use about_dialog::AboutDialog;
+use correct_match_dialog::CorrectMatchDialog;
 use gettextrs::gettext;
 use gtk::glib::clone;
 use gtk::{Application, gdk};
 use libadwaita::prelude::*;
 use log::{error, info};
 use logs_window::LogsWindow;
-use tokio::sync::Mutex;
-use tokio::time;
 
 use crate::TundraApp;
 use crate::app::PlayedTitle;
-use crate::clients::PictureUrl;
+use crate::clients::mal_client::MalClient;
+use crate::clients::{AnimeId, PictureUrl};
 use crate::constants::{REFRESH_INTERVAL, USER_AGENT};
 use crate::gtk_gui::main_window::MainWindow;
+use crate::gtk_gui::played_title_view::PlayedTitleInfo;
+use crate::title_recognizer::Title;
 
 mod about_dialog;
+mod correct_match_dialog;
 mod login_page;
 mod logs_window;
 mod main_window;
+mod played_title_view;
 mod scrobble_page;
 
 macro_rules! try_tx {
@@ -46,10 +50,12 @@ const DEFAULT_CHANNEL_SIZE: usize = 1;
 #[derive(Clone)]
 pub struct GtkApp {
     gtk_application: Application,
-    app: Arc<Mutex<TundraApp>>,
+    app: Arc<TundraApp>,
     main_window: Rc<MainWindow>,
     images: Arc<RwLock<HashMap<PictureUrl, gtk::glib::Bytes>>>,
-    current_image_url: Rc<RefCell<PictureUrl>>,
+    /// Pictures currently shown for each of the played titles
+    current_image_urls: Rc<RefCell<Vec<PictureUrl>>>,
+    played_titles: Rc<RefCell<Vec<PlayedTitle>>>,
     scrobbling_enabled: Arc<AtomicBool>,
 }
 
@@ -63,7 +69,7 @@ impl GtkApp {
             libadwaita::init().expect("Could not initialize libadwaita");
         });
 
-        let rc_app = Arc::new(Mutex::new(app));
+        let rc_app = Arc::new(app);
 
         application.connect_activate(move |gtk_application| {
             let mut gtk_app = Self {
@@ -71,7 +77,8 @@ impl GtkApp {
                 gtk_application: gtk_application.clone(),
                 main_window: Rc::new(MainWindow::new(gtk_application)),
                 images: Arc::new(RwLock::new(HashMap::new())),
-                current_image_url: Rc::new(RefCell::new(PictureUrl::default())),
+                current_image_urls: Rc::new(RefCell::new(Vec::new())),
+                played_titles: Rc::new(RefCell::new(Vec::new())),
                 scrobbling_enabled: Arc::new(AtomicBool::new(false)),
             };
             gtk_app.build_ui();
@@ -96,11 +103,27 @@ impl GtkApp {
                 this.set_scrobbling_enabled(state);
                 if !state {
                     this.main_window.set_anime_info_none();
-                    this.current_image_url.replace(PictureUrl::default());
+                    this.current_image_urls.borrow_mut().clear();
+                    this.played_titles.borrow_mut().clear();
                 }
             }
         ));
 
+        self.main_window.connect_confirm_match(clone!(
+            #[strong(rename_to = this)]
+            self,
+            move |index| {
+                this.confirm_match(index);
+            }
+        ));
+        self.main_window.connect_correct_match(clone!(
+            #[strong(rename_to = this)]
+            self,
+            move |index| {
+                this.correct_match(index);
+            }
+        ));
+
         let app = self.gtk_application.clone();
         let window = self.main_window.window();
 
@@ -146,7 +169,6 @@ impl GtkApp {
         let (tx, mut rx) = tokio::sync::mpsc::channel(DEFAULT_CHANNEL_SIZE);
         let app = self.app.clone();
         tokio::spawn(async move {
-            let app = app.lock().await;
             let result = app.is_mal_authenticated();
             tx.send(result)
                 .await
@@ -185,7 +207,6 @@ impl GtkApp {
         let (tx, mut rx) = tokio::sync::mpsc::channel(DEFAULT_CHANNEL_SIZE);
         let app = self.app.clone();
         tokio::spawn(async move {
-            let mut app = app.lock().await;
             let authenticator = try_tx!(tx, app.start_mal_authentication().await);
             tx.send(Ok(LoginAction::OpenBrowser(authenticator.get_auth_url())))
                 .await
@@ -221,21 +242,80 @@ impl GtkApp {
         });
     }
 
+    fn confirm_match(&self, index: usize) {
+        let Some(played_title) = self.played_titles.borrow().get(index).cloned() else {
+            return;
+        };
+
+        self.remember_match(played_title.title, played_title.anime_info.id);
+    }
+
+    fn correct_match(&self, index: usize) {
+        let Some(played_title) = self.played_titles.borrow().get(index).cloned() else {
+            return;
+        };
+
+        let dialog = CorrectMatchDialog::new(
+            &self.gtk_application,
+            &self.main_window.window(),
+            &played_title.title.title,
+        );
+        let this = self.clone();
+        dialog.connect_save(move |input| match MalClient::parse_anime_id(&input) {
+            Some(anime_id) => this.remember_match(played_title.title.clone(), anime_id),
+            None => this
+                .main_window
+                .show_error(&gettext("This is not a valid MyAnimeList URL or ID.")),
+        });
+        dialog.show();
+    }
+
+    fn remember_match(&self, title: Title, anime_id: AnimeId) {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(DEFAULT_CHANNEL_SIZE);
+        let app = self.app.clone();
+        tokio::spawn(async move {
+            let result = app
+                .remember_match(&title, anime_id)
+                .map_err(|e| e.to_string());
+            tx.send(result)
+                .await
+                .expect("Couldn't send data to channel");
+        });
+
+        let this = self.clone();
+        gtk::glib::spawn_future_local(async move {
+            let result = rx.recv().await.expect("Couldn't receive data from channel");
+            match result {
+                Ok(()) => this.main_window.show_info(&gettext(
+                    "The match has been saved and will be used from now on.",
+                )),
+                Err(error_string) => this.main_window.show_error(&error_string),
+            }
+        });
+    }
+
     fn run_daemon(&mut self) {
         let app = self.app.clone();
         let images = self.images.clone();
         let scrobbling_enabled = self.scrobbling_enabled.clone();
         let (tx, mut rx) = tokio::sync::mpsc::channel(DEFAULT_CHANNEL_SIZE);
         tokio::spawn(async move {
-            let mut interval = time::interval(REFRESH_INTERVAL);
+            if let Err(e) = app.start_webhook_server().await {
+                error!("{e:?}");
+            }
+            let mut player_events = app.player_events().await;
 
             loop {
-                interval.tick().await;
                 if !scrobbling_enabled.load(Ordering::Relaxed) {
+                    player_events.wait(REFRESH_INTERVAL).await;
                     continue;
                 }
 
                 let result = Self::daemon_tick(&app, &images).await;
+                let interval = match &result {
+                    Ok(played_titles) => TundraApp::refresh_interval(&player_events, played_titles),
+                    Err(_) => REFRESH_INTERVAL,
+                };
                 let new_result = result.map_err(|error| {
                     error!("{error}");
                     if let Some(source) = error.source() {
@@ -246,28 +326,35 @@ impl GtkApp {
                 tx.send(new_result)
                     .await
                     .expect("Couldn't send data to channel");
+
+                player_events.wait(interval).await;
             }
         });
 
         let main_window = self.main_window.clone();
         let images = self.images.clone();
-        let current_image_url = self.current_image_url.clone();
+        let current_image_urls = self.current_image_urls.clone();
+        let played_titles = self.played_titles.clone();
         gtk::glib::spawn_future_local(async move {
             while let Some(result) = rx.recv().await {
-                Self::handle_ui_daemon_tick(&result, &main_window, &images, &current_image_url);
+                Self::handle_ui_daemon_tick(
+                    &result,
+                    &main_window,
+                    &images,
+                    &current_image_urls,
+                    &played_titles,
+                );
             }
         });
     }
 
     async fn daemon_tick(
-        app: &Arc<Mutex<TundraApp>>,
+        app: &TundraApp,
         images: &Arc<RwLock<HashMap<PictureUrl, gtk::glib::Bytes>>>,
-    ) -> anyhow::Result<Option<PlayedTitle>> {
-        let mut app = app.lock().await;
-        app.try_scrobble().await?;
-        let played_title = app.get_played_title().await?;
+    ) -> anyhow::Result<Vec<PlayedTitle>> {
+        let played_titles = app.try_scrobble().await?;
 
-        if let Some(played_title) = played_title.clone() {
+        for played_title in &played_titles {
             let picture_url = &played_title.anime_info.picture;
             let image_downloaded = images.read().unwrap().contains_key(picture_url);
             if !image_downloaded {
@@ -281,7 +368,7 @@ impl GtkApp {
             }
         }
 
-        Ok(played_title)
+        Ok(played_titles)
     }
 
     async fn get_picture(url: &PictureUrl) -> anyhow::Result<bytes::Bytes> {
@@ -290,43 +377,56 @@ impl GtkApp {
     }
 
     fn handle_ui_daemon_tick(
-        result: &Result<Option<PlayedTitle>, String>,
+        result: &Result<Vec<PlayedTitle>, String>,
         main_window: &Rc<MainWindow>,
         images: &Arc<RwLock<HashMap<PictureUrl, gtk::glib::Bytes>>>,
-        current_image_url: &Rc<RefCell<PictureUrl>>,
+        current_image_urls: &Rc<RefCell<Vec<PictureUrl>>>,
+        played_titles: &Rc<RefCell<Vec<PlayedTitle>>>,
     ) {
-        if let Err(error_string) = result {
-            main_window.show_error(error_string);
-        } else if let Ok(Some(result)) = result {
+        let result = match result {
+            Ok(result) => result,
+            Err(error_string) => {
+                played_titles.borrow_mut().clear();
+                main_window.show_error(error_string);
+                return;
+            }
+        };
+        played_titles.replace(result.clone());
+
+        main_window.set_played_title_count(result.len());
+        let mut current_image_urls = current_image_urls.borrow_mut();
+        current_image_urls.resize(result.len(), PictureUrl::default());
+
+        for (index, result) in result.iter().enumerate() {
             let anime_info = &result.anime_info;
-            let title = &anime_info.title;
-            let episode_number = &anime_info.episode_watched.to_string();
-            let player_name = &result.player_name;
             let status = if result.scrobbled {
                 gettext("scrobbled")
+            } else if result.uncertain {
+                gettext("uncertain match, will not be scrobbled")
+            } else if let Some(time) = result.time_until_scrobble {
+                // Rounded up, so that it is not shown as 0 before scrobbling
+                let minutes = time.as_secs().div_ceil(60);
+                gettext("not yet scrobbled, about {} min left").replace("{}", &minutes.to_string())
             } else {
                 gettext("not yet scrobbled")
             };
+            let info = PlayedTitleInfo {
+                title: anime_info.title.clone(),
+                episode: anime_info.episode_watched.to_string(),
+                player_name: result.player_name.clone(),
+                status,
+                confidence: anime_info.confidence.to_string(),
+                website_url: anime_info.website_url.0.clone(),
+            };
 
-            let website_url = &anime_info.website_url;
-            let picture = if *current_image_url.borrow() != anime_info.picture {
-                current_image_url.replace(anime_info.picture.clone());
+            let picture = if current_image_urls[index] != anime_info.picture {
+                current_image_urls[index] = anime_info.picture.clone();
                 Some(images.read().unwrap()[&anime_info.picture].clone())
             } else {
                 None
             };
 
-            main_window.set_anime_info(
-                title,
-                episode_number,
-                player_name,
-                &status,
-                website_url,
-                picture,
-            );
-        } else {
-            main_window.set_anime_info_none();
-            current_image_url.replace(PictureUrl::default());
+            main_window.set_anime_info(index, &info, picture);
         }
     }
 }
@@ -0,0 +1,267 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::LazyLock;
+
+use gettextrs::gettext;
+use glib::subclass::Signal;
+use glib::{ParamSpec, ParamSpecObject, ParamSpecString, Value, clone};
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::{gdk, glib};
+
+#[derive(Default)]
+pub struct PlayedTitleView {
+    title_label: Rc<RefCell<gtk::Label>>,
+    episode_label: Rc<RefCell<gtk::Label>>,
+    player_label: Rc<RefCell<gtk::Label>>,
+    status_label: Rc<RefCell<gtk::Label>>,
+    confidence_label: Rc<RefCell<gtk::Label>>,
+    website_url: Rc<RefCell<String>>,
+    picture: Rc<RefCell<gtk::Picture>>,
+}
+
+impl PlayedTitleView {
+    fn make_label(text: &str) -> gtk::Label {
+        let label = gtk::Label::new(None);
+        label.set_markup(&format!("<i>{text}</i>"));
+        label.set_halign(gtk::Align::End);
+        label.set_valign(gtk::Align::Start);
+        label
+    }
+
+    fn make_property_label() -> gtk::Label {
+        let label = gtk::Label::new(None);
+        label.set_halign(gtk::Align::Start);
+        label.set_wrap(true);
+        label
+    }
+
+    fn make_picture(&self) -> gtk::Picture {
+        let picture = gtk::Picture::new();
+        picture.set_hexpand(true);
+        picture.set_vexpand(true);
+
+        let gesture = gtk::GestureClick::new();
+        let website_url = self.website_url.clone();
+        gesture.connect_released(clone!(
+            #[strong]
+            website_url,
+            move |gesture, _, _, _| {
+                gesture.set_state(gtk::EventSequenceState::Claimed);
+                let url = website_url.borrow();
+                if !url.is_empty() {
+                    gtk::show_uri(gtk::Window::NONE, &url, gdk::CURRENT_TIME);
+                }
+            }
+        ));
+        picture.add_controller(gesture);
+
+        *self.picture.borrow_mut() = picture.clone();
+
+        picture
+    }
+
+    fn make_match_buttons(&self) -> gtk::Box {
+        let confirm_button = gtk::Button::with_mnemonic(&gettext("_Confirm match"));
+        confirm_button.set_tooltip_text(Some(&gettext(
+            "Always use this anime for the title being played",
+        )));
+        let this = self.to_owned();
+        confirm_button.connect_clicked(clone!(
+            #[strong]
+            this,
+            move |_| {
+                this.obj()
+                    .emit_by_name::<()>(super::PlayedTitleView::CONFIRM_MATCH_SIGNAL, &[]);
+            }
+        ));
+
+        let correct_button = gtk::Button::with_mnemonic(&gettext("_Wrong match…"));
+        correct_button.set_tooltip_text(Some(&gettext("Choose the anime being played manually")));
+        correct_button.connect_clicked(clone!(
+            #[strong]
+            this,
+            move |_| {
+                this.obj()
+                    .emit_by_name::<()>(super::PlayedTitleView::CORRECT_MATCH_SIGNAL, &[]);
+            }
+        ));
+
+        let match_buttons = gtk::Box::new(gtk::Orientation::Horizontal, 5);
+        match_buttons.set_halign(gtk::Align::Center);
+        match_buttons.set_margin_top(7);
+        match_buttons.append(&confirm_button);
+        match_buttons.append(&correct_button);
+
+        match_buttons
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for PlayedTitleView {
+    type ParentType = gtk::Box;
+    type Type = super::PlayedTitleView;
+
+    const NAME: &'static str = "TundraPlayedTitleView";
+}
+
+impl ObjectImpl for PlayedTitleView {
+    fn constructed(&self) {
+        self.parent_constructed();
+
+        let obj = self.obj();
+        obj.set_orientation(gtk::Orientation::Horizontal);
+        obj.set_homogeneous(true);
+        obj.set_spacing(15);
+
+        let grid = gtk::Grid::new();
+        grid.set_column_spacing(10);
+        grid.set_row_spacing(3);
+
+        grid.attach(&Self::make_label(&gettext("Title:")), 0, 0, 1, 1);
+        let title_label = Self::make_property_label();
+        grid.attach(&title_label, 1, 0, 1, 1);
+        *self.title_label.borrow_mut() = title_label;
+
+        grid.attach(&Self::make_label(&gettext("Episode:")), 0, 1, 1, 1);
+        let episode_label = Self::make_property_label();
+        grid.attach(&episode_label, 1, 1, 1, 1);
+        *self.episode_label.borrow_mut() = episode_label;
+
+        grid.attach(&Self::make_label(&gettext("Player:")), 0, 2, 1, 1);
+        let player_label = Self::make_property_label();
+        grid.attach(&player_label, 1, 2, 1, 1);
+        *self.player_label.borrow_mut() = player_label;
+
+        grid.attach(&Self::make_label(&gettext("Status:")), 0, 3, 1, 1);
+        let status_label = Self::make_property_label();
+        grid.attach(&status_label, 1, 3, 1, 1);
+        *self.status_label.borrow_mut() = status_label;
+
+        grid.attach(&Self::make_label(&gettext("Confidence:")), 0, 4, 1, 1);
+        let confidence_label = Self::make_property_label();
+        grid.attach(&confidence_label, 1, 4, 1, 1);
+        *self.confidence_label.borrow_mut() = confidence_label;
+
+        grid.attach(&self.make_match_buttons(), 0, 5, 2, 1);
+
+        grid.set_halign(gtk::Align::Center);
+        grid.set_valign(gtk::Align::Center);
+        grid.set_vexpand(true);
+
+        obj.append(&self.make_picture());
+        obj.append(&grid);
+    }
+
+    fn properties() -> &'static [ParamSpec] {
+        static PROPERTIES: LazyLock<Vec<ParamSpec>> = LazyLock::new(|| {
+            vec![
+                ParamSpecString::builder(super::PlayedTitleView::TITLE_PROPERTY)
+                    .blurb("Anime title")
+                    .default_value(Some(""))
+                    .flags(glib::ParamFlags::READWRITE)
+                    .build(),
+                ParamSpecString::builder(super::PlayedTitleView::EPISODE_PROPERTY)
+                    .blurb("Anime episode")
+                    .default_value(Some(""))
+                    .flags(glib::ParamFlags::READWRITE)
+                    .build(),
+                ParamSpecString::builder(super::PlayedTitleView::PLAYER_PROPERTY)
+                    .blurb("Player being used")
+                    .default_value(Some(""))
+                    .flags(glib::ParamFlags::READWRITE)
+                    .build(),
+                ParamSpecString::builder(super::PlayedTitleView::STATUS_PROPERTY)
+                    .blurb("Scrobble status")
+                    .default_value(Some(""))
+                    .flags(glib::ParamFlags::READWRITE)
+                    .build(),
+                ParamSpecString::builder(super::PlayedTitleView::CONFIDENCE_PROPERTY)
+                    .blurb("Match confidence")
+                    .default_value(Some(""))
+                    .flags(glib::ParamFlags::READWRITE)
+                    .build(),
+                ParamSpecString::builder(super::PlayedTitleView::WEBSITE_URL_PROPERTY)
+                    .blurb("Anime website URL")
+                    .default_value(Some(""))
+                    .flags(glib::ParamFlags::READWRITE)
+                    .build(),
+                ParamSpecObject::builder::<gdk::Paintable>(super::PlayedTitleView::IMAGE_PROPERTY)
+                    .blurb("Image paintable")
+                    .flags(glib::ParamFlags::READWRITE)
+                    .build(),
+            ]
+        });
+        PROPERTIES.as_ref()
+    }
+
+    fn signals() -> &'static [Signal] {
+        static SIGNALS: LazyLock<Vec<Signal>> = LazyLock::new(|| {
+            vec![
+                Signal::builder(super::PlayedTitleView::CONFIRM_MATCH_SIGNAL).build(),
+                Signal::builder(super::PlayedTitleView::CORRECT_MATCH_SIGNAL).build(),
+            ]
+        });
+        SIGNALS.as_ref()
+    }
+
+    fn property(&self, _id: usize, pspec: &ParamSpec) -> Value {
+        match pspec.name() {
+            super::PlayedTitleView::TITLE_PROPERTY => self.title_label.borrow().text().to_value(),
+            super::PlayedTitleView::EPISODE_PROPERTY => {
+                self.episode_label.borrow().text().to_value()
+            }
+            super::PlayedTitleView::PLAYER_PROPERTY => self.player_label.borrow().text().to_value(),
+            super::PlayedTitleView::STATUS_PROPERTY => self.status_label.borrow().text().to_value(),
+            super::PlayedTitleView::CONFIDENCE_PROPERTY => {
+                self.confidence_label.borrow().text().to_value()
+            }
+            super::PlayedTitleView::WEBSITE_URL_PROPERTY => self.website_url.borrow().to_value(),
+            super::PlayedTitleView::IMAGE_PROPERTY => self.picture.borrow().paintable().to_value(),
+            _ => unimplemented!(),
+        }
+    }
+
+    fn set_property(&self, _id: usize, value: &Value, pspec: &ParamSpec) {
+        match pspec.name() {
+            super::PlayedTitleView::TITLE_PROPERTY => {
+                self.title_label.borrow().set_text(value.get().unwrap());
+            }
+            super::PlayedTitleView::EPISODE_PROPERTY => {
+                self.episode_label.borrow().set_text(value.get().unwrap());
+            }
+            super::PlayedTitleView::PLAYER_PROPERTY => {
+                self.player_label.borrow().set_text(value.get().unwrap());
+            }
+            super::PlayedTitleView::STATUS_PROPERTY => {
+                self.status_label.borrow().set_text(value.get().unwrap());
+            }
+            super::PlayedTitleView::CONFIDENCE_PROPERTY => {
+                self.confidence_label
+                    .borrow()
+                    .set_text(value.get().unwrap());
+            }
+            super::PlayedTitleView::WEBSITE_URL_PROPERTY => {
+                let website_url: String = value.get().unwrap();
+
+                let image = self.picture.borrow();
+                if !website_url.is_empty() {
+                    image.set_cursor_from_name(Some("pointer"));
+                } else {
+                    image.set_cursor_from_name(Some("default"));
+                }
+
+                *self.website_url.borrow_mut() = website_url;
+            }
+            super::PlayedTitleView::IMAGE_PROPERTY => {
+                let x: Option<&gdk::Paintable> = value.get().ok();
+                self.picture.borrow().set_paintable(x);
+            }
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl WidgetImpl for PlayedTitleView {}
+
+impl BoxImpl for PlayedTitleView {}
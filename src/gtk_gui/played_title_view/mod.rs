@@ -0,0 +1,93 @@
+use glib::Object;
+use glib::object::ObjectExt;
+use gtk::{gdk, glib};
+
+mod imp;
+
+glib::wrapper! {
+    /// Anime being played by a single player.
+    pub struct PlayedTitleView(ObjectSubclass<imp::PlayedTitleView>)
+        @extends gtk::Box, gtk::Widget,
+        @implements gtk::Accessible, gtk::Actionable, gtk::Buildable, gtk::ConstraintTarget, gtk::Orientable;
+}
+
+/// Texts describing a played title.
+pub struct PlayedTitleInfo {
+    pub title: String,
+    pub episode: String,
+    pub player_name: String,
+    pub status: String,
+    pub confidence: String,
+    pub website_url: String,
+}
+
+#[allow(clippy::new_without_default)]
+impl PlayedTitleView {
+    pub const CONFIDENCE_PROPERTY: &'static str = "confidence";
+    pub const CONFIRM_MATCH_SIGNAL: &'static str = "confirm-match";
+    pub const CORRECT_MATCH_SIGNAL: &'static str = "correct-match";
+    pub const EPISODE_PROPERTY: &'static str = "episode";
+    pub const IMAGE_PROPERTY: &'static str = "image";
+    pub const PLAYER_PROPERTY: &'static str = "player";
+    pub const STATUS_PROPERTY: &'static str = "status";
+    pub const TITLE_PROPERTY: &'static str = "title";
+    pub const WEBSITE_URL_PROPERTY: &'static str = "website-url";
+
+    pub fn new() -> Self {
+        Object::new::<Self>()
+    }
+
+    pub fn set_anime_info(&self, info: &PlayedTitleInfo, picture: Option<gdk::Texture>) {
+        self.set_title(&info.title);
+        self.set_episode(&info.episode);
+        self.set_player(&info.player_name);
+        self.set_status(&info.status);
+        self.set_confidence(&info.confidence);
+        self.set_website_url(&info.website_url);
+        if picture.is_some() {
+            self.set_image(picture);
+        }
+    }
+
+    pub fn connect_confirm_match<F: Fn() + 'static>(&self, f: F) {
+        self.connect_local(Self::CONFIRM_MATCH_SIGNAL, false, move |_args| {
+            f();
+            None
+        });
+    }
+
+    pub fn connect_correct_match<F: Fn() + 'static>(&self, f: F) {
+        self.connect_local(Self::CORRECT_MATCH_SIGNAL, false, move |_args| {
+            f();
+            None
+        });
+    }
+
+    fn set_title(&self, title: &str) {
+        self.set_property(Self::TITLE_PROPERTY, title);
+    }
+
+    fn set_episode(&self, episode: &str) {
+        self.set_property(Self::EPISODE_PROPERTY, episode);
+    }
+
+    fn set_player(&self, player: &str) {
+        self.set_property(Self::PLAYER_PROPERTY, player);
+    }
+
+    fn set_status(&self, status: &str) {
+        self.set_property(Self::STATUS_PROPERTY, status);
+    }
+
+    fn set_confidence(&self, confidence: &str) {
+        self.set_property(Self::CONFIDENCE_PROPERTY, confidence);
+    }
+
+    fn set_website_url(&self, website_url: &str) {
+        self.set_property(Self::WEBSITE_URL_PROPERTY, website_url);
+    }
+
+    fn set_image(&self, image: Option<gdk::Texture>) {
+        self.set_property(Self::IMAGE_PROPERTY, image);
+    }
+}
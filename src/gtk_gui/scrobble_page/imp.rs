@@ -2,42 +2,26 @@ use std::cell::RefCell;
 use std::rc::Rc;
 use std::sync::LazyLock;
 
-use gettextrs::gettext;
-use glib::{ParamSpec, ParamSpecObject, ParamSpecString, Value, clone};
+use glib::subclass::Signal;
+use glib::{ParamSpec, ParamSpecString, Value, clone};
+use gtk::glib;
 use gtk::prelude::*;
 use gtk::subclass::prelude::*;
-use gtk::{gdk, glib};
+
+use crate::gtk_gui::played_title_view::PlayedTitleView;
 
 #[derive(Default)]
 pub struct ScrobblePage {
     status_summary_label: Rc<RefCell<gtk::Label>>,
-    title_label: Rc<RefCell<gtk::Label>>,
-    episode_label: Rc<RefCell<gtk::Label>>,
-    player_label: Rc<RefCell<gtk::Label>>,
-    status_label: Rc<RefCell<gtk::Label>>,
-    website_url: Rc<RefCell<String>>,
-    picture: Rc<RefCell<gtk::Picture>>,
+    views_box: Rc<RefCell<gtk::Box>>,
+    views: Rc<RefCell<Vec<PlayedTitleView>>>,
 }
 
 impl ScrobblePage {
-    fn make_label(text: &str) -> gtk::Label {
-        let label = gtk::Label::new(None);
-        label.set_markup(&format!("<i>{text}</i>"));
-        label.set_halign(gtk::Align::End);
-        label.set_valign(gtk::Align::Start);
-        label
-    }
-
     fn make_status_summary_label() -> gtk::Label {
         let label = gtk::Label::new(None);
         label.set_halign(gtk::Align::Center);
-        label
-    }
-
-    fn make_property_label() -> gtk::Label {
-        let label = gtk::Label::new(None);
-        label.set_halign(gtk::Align::Start);
-        label.set_wrap(true);
+        label.set_margin_top(10);
         label
     }
 
@@ -45,29 +29,62 @@ impl ScrobblePage {
         format!("<b>{}</b>", value.get::<&str>().unwrap())
     }
 
-    fn make_picture(&self) -> gtk::Picture {
-        let picture = gtk::Picture::new();
-        picture.set_hexpand(true);
-        picture.set_vexpand(true);
-
-        let gesture = gtk::GestureClick::new();
-        let website_url = self.website_url.clone();
-        gesture.connect_released(clone!(
-            #[strong]
-            website_url,
-            move |gesture, _, _, _| {
-                gesture.set_state(gtk::EventSequenceState::Claimed);
-                let url = website_url.borrow();
-                if !url.is_empty() {
-                    gtk::show_uri(gtk::Window::NONE, &url, gdk::CURRENT_TIME);
+    fn make_views(&self) -> gtk::ScrolledWindow {
+        let views_box = gtk::Box::new(gtk::Orientation::Vertical, 20);
+        views_box.set_margin_start(10);
+        views_box.set_margin_end(10);
+        views_box.set_margin_top(10);
+        views_box.set_margin_bottom(10);
+
+        let scrolled_window = gtk::ScrolledWindow::new();
+        scrolled_window.set_hscrollbar_policy(gtk::PolicyType::Never);
+        scrolled_window.set_vexpand(true);
+        scrolled_window.set_child(Some(&views_box));
+
+        *self.views_box.borrow_mut() = views_box;
+
+        scrolled_window
+    }
+
+    /// Adds or removes the views, so that there is one for each of `count`
+    /// played titles.
+    pub(super) fn set_view_count(&self, count: usize) {
+        let mut views = self.views.borrow_mut();
+        let views_box = self.views_box.borrow();
+
+        while views.len() > count {
+            views_box.remove(&views.pop().unwrap());
+        }
+
+        while views.len() < count {
+            let index = views.len() as u32;
+            let view = PlayedTitleView::new();
+
+            let this = self.to_owned();
+            view.connect_confirm_match(clone!(
+                #[strong]
+                this,
+                move || {
+                    this.obj()
+                        .emit_by_name::<()>(super::ScrobblePage::CONFIRM_MATCH_SIGNAL, &[&index]);
                 }
-            }
-        ));
-        picture.add_controller(gesture);
+            ));
+            view.connect_correct_match(clone!(
+                #[strong]
+                this,
+                move || {
+                    this.obj()
+                        .emit_by_name::<()>(super::ScrobblePage::CORRECT_MATCH_SIGNAL, &[&index]);
+                }
+            ));
 
-        *self.picture.borrow_mut() = picture.clone();
+            views_box.append(&view);
+            views.push(view);
+        }
+    }
 
-        picture
+    pub(super) fn view(&self, index: usize) -> Option<PlayedTitleView> {
+        self.views.borrow().get(index).cloned()
     }
 }
 
@@ -84,48 +101,13 @@ impl ObjectImpl for ScrobblePage {
         self.parent_constructed();
 
         let obj = self.obj();
-        obj.set_orientation(gtk::Orientation::Horizontal);
-        obj.set_homogeneous(true);
-        obj.set_spacing(15);
-        obj.set_margin_start(10);
-        obj.set_margin_end(10);
-        obj.set_margin_top(10);
-        obj.set_margin_bottom(10);
-
-        let grid = gtk::Grid::new();
-        grid.set_column_spacing(10);
-        grid.set_row_spacing(3);
+        obj.set_orientation(gtk::Orientation::Vertical);
 
         let status_summary_label = Self::make_status_summary_label();
-        grid.attach(&status_summary_label, 0, 0, 2, 1);
+        obj.append(&status_summary_label);
         *self.status_summary_label.borrow_mut() = status_summary_label;
 
-        grid.attach(&Self::make_label(&gettext("Title:")), 0, 1, 1, 1);
-        let title_label = Self::make_property_label();
-        grid.attach(&title_label, 1, 1, 1, 1);
-        *self.title_label.borrow_mut() = title_label;
-
-        grid.attach(&Self::make_label(&gettext("Episode:")), 0, 2, 1, 1);
-        let episode_label = Self::make_property_label();
-        grid.attach(&episode_label, 1, 2, 1, 1);
-        *self.episode_label.borrow_mut() = episode_label;
-
-        grid.attach(&Self::make_label(&gettext("Player:")), 0, 3, 1, 1);
-        let player_label = Self::make_property_label();
-        grid.attach(&player_label, 1, 3, 1, 1);
-        *self.player_label.borrow_mut() = player_label;
-
-        grid.attach(&Self::make_label(&gettext("Status:")), 0, 4, 1, 1);
-        let status_label = Self::make_property_label();
-        grid.attach(&status_label, 1, 4, 1, 1);
-        *self.status_label.borrow_mut() = status_label;
-
-        grid.set_halign(gtk::Align::Center);
-        grid.set_valign(gtk::Align::Center);
-        grid.set_vexpand(true);
-
-        obj.append(&self.make_picture());
-        obj.append(&grid);
+        obj.append(&self.make_views());
     }
 
     fn properties() -> &'static [ParamSpec] {
@@ -136,38 +118,23 @@ impl ObjectImpl for ScrobblePage {
                     .default_value(Some(""))
                     .flags(glib::ParamFlags::READWRITE)
                     .build(),
-                ParamSpecString::builder(super::ScrobblePage::TITLE_PROPERTY)
-                    .blurb("Anime title")
-                    .default_value(Some(""))
-                    .flags(glib::ParamFlags::READWRITE)
-                    .build(),
-                ParamSpecString::builder(super::ScrobblePage::EPISODE_PROPERTY)
-                    .blurb("Anime episode")
-                    .default_value(Some(""))
-                    .flags(glib::ParamFlags::READWRITE)
-                    .build(),
-                ParamSpecString::builder(super::ScrobblePage::PLAYER_PROPERTY)
-                    .blurb("Player being used")
-                    .default_value(Some(""))
-                    .flags(glib::ParamFlags::READWRITE)
-                    .build(),
-                ParamSpecString::builder(super::ScrobblePage::STATUS_PROPERTY)
-                    .blurb("Scrobble status")
-                    .default_value(Some(""))
-                    .flags(glib::ParamFlags::READWRITE)
-                    .build(),
-                ParamSpecString::builder(super::ScrobblePage::WEBSITE_URL_PROPERTY)
-                    .blurb("Anime website URL")
-                    .default_value(Some(""))
-                    .flags(glib::ParamFlags::READWRITE)
+            ]
+        });
+        PROPERTIES.as_ref()
+    }
+
+    fn signals() -> &'static [Signal] {
+        static SIGNALS: LazyLock<Vec<Signal>> = LazyLock::new(|| {
+            vec![
+                Signal::builder(super::ScrobblePage::CONFIRM_MATCH_SIGNAL)
+                    .param_types([u32::static_type()])
                     .build(),
-                ParamSpecObject::builder::<gdk::Paintable>(super::ScrobblePage::IMAGE_PROPERTY)
-                    .blurb("Image paintable")
-                    .flags(glib::ParamFlags::READWRITE)
+                Signal::builder(super::ScrobblePage::CORRECT_MATCH_SIGNAL)
+                    .param_types([u32::static_type()])
                     .build(),
             ]
         });
-        PROPERTIES.as_ref()
+        SIGNALS.as_ref()
     }
 
     fn property(&self, _id: usize, pspec: &ParamSpec) -> Value {
@@ -175,12 +142,6 @@ impl ObjectImpl for ScrobblePage {
             super::ScrobblePage::STATUS_SUMMARY_PROPERTY => {
                 self.status_summary_label.borrow().text().to_value()
             }
-            super::ScrobblePage::TITLE_PROPERTY => self.title_label.borrow().text().to_value(),
-            super::ScrobblePage::EPISODE_PROPERTY => self.episode_label.borrow().text().to_value(),
-            super::ScrobblePage::PLAYER_PROPERTY => self.player_label.borrow().text().to_value(),
-            super::ScrobblePage::STATUS_PROPERTY => self.status_label.borrow().text().to_value(),
-            super::ScrobblePage::WEBSITE_URL_PROPERTY => self.website_url.borrow().to_value(),
-            super::ScrobblePage::IMAGE_PROPERTY => self.picture.borrow().paintable().to_value(),
             _ => unimplemented!(),
         }
     }
@@ -191,34 +152,6 @@ impl ObjectImpl for ScrobblePage {
                 .status_summary_label
                 .borrow()
                 .set_markup(&Self::get_status_summary_label_markup(value)),
-            super::ScrobblePage::TITLE_PROPERTY => {
-                self.title_label.borrow().set_text(value.get().unwrap());
-            }
-            super::ScrobblePage::EPISODE_PROPERTY => {
-                self.episode_label.borrow().set_text(value.get().unwrap());
-            }
-            super::ScrobblePage::PLAYER_PROPERTY => {
-                self.player_label.borrow().set_text(value.get().unwrap());
-            }
-            super::ScrobblePage::STATUS_PROPERTY => {
-                self.status_label.borrow().set_text(value.get().unwrap());
-            }
-            super::ScrobblePage::WEBSITE_URL_PROPERTY => {
-                let website_url: String = value.get().unwrap();
-
-                let image = self.picture.borrow();
-                if !website_url.is_empty() {
-                    image.set_cursor_from_name(Some("pointer"));
-                } else {
-                    image.set_cursor_from_name(Some("default"));
-                }
-
-                *self.website_url.borrow_mut() = website_url;
-            }
-            super::ScrobblePage::IMAGE_PROPERTY => {
-                let x: Option<&gdk::Paintable> = value.get().ok();
-                self.picture.borrow().set_paintable(x);
-            }
             _ => unimplemented!(),
         }
     }
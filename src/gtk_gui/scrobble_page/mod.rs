@@ -1,8 +1,11 @@
 use gettextrs::gettext;
 use glib::Object;
 use glib::object::ObjectExt;
+use gtk::subclass::prelude::*;
 use gtk::{gdk, glib};
 
+use crate::gtk_gui::played_title_view::PlayedTitleInfo;
+
 mod imp;
 
 glib::wrapper! {
@@ -13,13 +16,9 @@ glib::wrapper! {
 
 #[allow(clippy::new_without_default)]
 impl ScrobblePage {
-    pub const EPISODE_PROPERTY: &'static str = "episode";
-    pub const IMAGE_PROPERTY: &'static str = "image";
-    pub const PLAYER_PROPERTY: &'static str = "player";
-    pub const STATUS_PROPERTY: &'static str = "status";
+    pub const CONFIRM_MATCH_SIGNAL: &'static str = "confirm-match";
+    pub const CORRECT_MATCH_SIGNAL: &'static str = "correct-match";
     pub const STATUS_SUMMARY_PROPERTY: &'static str = "status-summary";
-    pub const TITLE_PROPERTY: &'static str = "title";
-    pub const WEBSITE_URL_PROPERTY: &'static str = "website-url";
 
     pub fn new() -> Self {
         let scrobble_page: Self = Object::new::<Self>();
@@ -27,61 +26,50 @@ impl ScrobblePage {
         scrobble_page
     }
 
+    /// Shows given number of played titles. Their info is set with
+    /// [`Self::set_anime_info`].
+    pub fn set_played_title_count(&self, count: usize) {
+        self.imp().set_view_count(count);
+        if count > 0 {
+            self.set_status_summary(&gettext("Scrobbling now"));
+        } else {
+            self.set_status_summary(&gettext("Not scrobbling now"));
+        }
+    }
+
     pub fn set_anime_info(
         &self,
-        title: &str,
-        episode_number: &str,
-        player_name: &str,
-        status: &str,
-        website_url: &str,
+        index: usize,
+        info: &PlayedTitleInfo,
         picture: Option<gdk::Texture>,
     ) {
-        self.set_status_summary(&gettext("Scrobbling now"));
-        self.set_title(title);
-        self.set_episode(episode_number);
-        self.set_player(player_name);
-        self.set_status(status);
-        self.set_website_url(website_url);
-        if picture.is_some() {
-            self.set_image(picture);
+        if let Some(view) = self.imp().view(index) {
+            view.set_anime_info(info, picture);
         }
     }
 
     pub fn set_anime_info_none(&self) {
-        self.set_status_summary(&gettext("Not scrobbling now"));
-        self.set_title(&gettext("N/A"));
-        self.set_episode(&gettext("N/A"));
-        self.set_player(&gettext("N/A"));
-        self.set_status(&gettext("N/A"));
-        self.set_website_url("");
-        self.set_image(None);
-    }
-
-    fn set_status_summary(&self, status_summary: &str) {
-        self.set_property(Self::STATUS_SUMMARY_PROPERTY, status_summary);
+        self.set_played_title_count(0);
     }
 
-    fn set_title(&self, title: &str) {
-        self.set_property(Self::TITLE_PROPERTY, title);
+    /// Calls `f` with the index of the played title whose match has been
+    /// confirmed.
+    pub fn connect_confirm_match<F: Fn(usize) + 'static>(&self, f: F) {
+        self.connect_local(Self::CONFIRM_MATCH_SIGNAL, false, move |args| {
+            f(args[1].get::<u32>().unwrap() as usize);
+            None
+        });
     }
 
-    fn set_episode(&self, episode: &str) {
-        self.set_property(Self::EPISODE_PROPERTY, episode);
+    /// Calls `f` with the index of the played title whose match is wrong.
+    pub fn connect_correct_match<F: Fn(usize) + 'static>(&self, f: F) {
+        self.connect_local(Self::CORRECT_MATCH_SIGNAL, false, move |args| {
+            f(args[1].get::<u32>().unwrap() as usize);
+            None
+        });
     }
 
-    fn set_player(&self, player: &str) {
-        self.set_property(Self::PLAYER_PROPERTY, player);
-    }
-
-    fn set_status(&self, status: &str) {
-        self.set_property(Self::STATUS_PROPERTY, status);
-    }
-
-    fn set_website_url(&self, website_url: &str) {
-        self.set_property(Self::WEBSITE_URL_PROPERTY, website_url);
-    }
-
-    fn set_image(&self, image: Option<gdk::Texture>) {
-        self.set_property(Self::IMAGE_PROPERTY, image);
+    fn set_status_summary(&self, status_summary: &str) {
+        self.set_property(Self::STATUS_SUMMARY_PROPERTY, status_summary);
     }
 }
@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::clients::AnimeId;
+use crate::config::Config;
+use crate::title_recognizer::Title;
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct LearnedMappingsFile {
+    #[serde(default)]
+    mappings: Vec<LearnedMapping>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct LearnedMapping {
+    title: String,
+    season: i32,
+    #[serde(default = "default_part")]
+    part: i32,
+    #[serde(default)]
+    final_season: bool,
+    #[serde(default)]
+    movie: bool,
+    anime_id: String,
+}
+
+fn default_part() -> i32 {
+    1
+}
+
+/// Normalized title, season number, part number, whether it is the final
+/// season and whether it is a movie.
+type MappingKey = (String, i32, i32, bool, bool);
+
+/// Matches confirmed or corrected by the user, keyed by the normalized
+/// recognized title and season. These are persisted, so that the
+/// same title is never searched again.
+#[derive(Debug)]
+pub struct LearnedMappings {
+    path: PathBuf,
+    mappings: HashMap<MappingKey, AnimeId>,
+}
+
+impl LearnedMappings {
+    pub fn load() -> Self {
+        Self::load_from(Config::data_path().join("learned_mappings.toml"))
+    }
+
+    /// Loads the mappings from given file, or starts with no mappings if it
+    /// does not exist yet.
+    pub fn load_from(path: PathBuf) -> Self {
+        let file: LearnedMappingsFile = match fs::read_to_string(&path) {
+            Ok(str) => toml::from_str(&str).unwrap_or_else(|e| {
+                warn!("Could not parse learned mappings: {e}");
+                LearnedMappingsFile::default()
+            }),
+            Err(_) => LearnedMappingsFile::default(),
+        };
+
+        let mappings = file
+            .mappings
+            .into_iter()
+            .map(|x| {
+                (
+                    (x.title, x.season, x.part, x.final_season, x.movie),
+                    AnimeId(x.anime_id),
+                )
+            })
+            .collect();
+
+        Self { path, mappings }
+    }
+
+    fn snapshot(&self) -> LearnedMappingsSnapshot {
+        let mut mappings: Vec<LearnedMapping> = self
+            .mappings
+            .iter()
+            .map(
+                |((title, season, part, final_season, movie), anime_id)| LearnedMapping {
+                    title: title.clone(),
+                    season: *season,
+                    part: *part,
+                    final_season: *final_season,
+                    movie: *movie,
+                    anime_id: anime_id.0.clone(),
+                },
+            )
+            .collect();
+        mappings.sort_by(|a, b| {
+            (&a.title, a.movie, a.final_season, a.season, a.part).cmp(&(
+                &b.title,
+                b.movie,
+                b.final_season,
+                b.season,
+                b.part,
+            ))
+        });
+
+        LearnedMappingsSnapshot {
+            path: self.path.clone(),
+            file: LearnedMappingsFile { mappings },
+        }
+    }
+
+    fn key(title: &Title) -> MappingKey {
+        (
+            title.normalized_key(),
+            title.season_number,
+            title.part_number,
+            title.final_season,
+            title.movie,
+        )
+    }
+
+    pub fn get(&self, title: &Title) -> Option<&AnimeId> {
+        self.mappings.get(&Self::key(title))
+    }
+
+    /// Adds the mapping and returns the mappings to be saved, so that the
+    /// file can be written after the lock on the mappings is released.
+    #[must_use = "the mappings have to be saved"]
+    pub fn insert(&mut self, title: &Title, anime_id: AnimeId) -> LearnedMappingsSnapshot {
+        info!(
+            "Learned {} season {} as anime {anime_id}",
+            title.title, title.season_number
+        );
+        self.mappings.insert(Self::key(title), anime_id);
+        self.snapshot()
+    }
+
+    /// Returns whether both titles refer to the same learned mapping key.
+    pub fn same_key(a: &Title, b: &Title) -> bool {
+        Self::key(a) == Self::key(b)
+    }
+}
+
+/// A copy of the learned mappings, as they are written to the file.
+pub struct LearnedMappingsSnapshot {
+    path: PathBuf,
+    file: LearnedMappingsFile,
+}
+
+impl LearnedMappingsSnapshot {
+    pub fn save(&self) -> anyhow::Result<()> {
+        let toml = toml::to_string(&self.file).context("Could not serialize learned mappings")?;
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).context("Could not create data directory")?;
+        }
+        fs::write(&self.path, toml).context("Could not write learned mappings to file")?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn title(title: &str, season_number: i32) -> Title {
+        Title::new(title.to_owned(), season_number, 1)
+    }
+
+    #[test]
+    fn saves_and_loads_mappings() {
+        let path = std::env::temp_dir().join(format!(
+            "tundra-learned-mappings-{}.toml",
+            std::process::id()
+        ));
+
+        let mut mappings = LearnedMappings::load_from(path.clone());
+        let mut part_2 = title("Frieren", 1);
+        part_2.part_number = 2;
+        let mut final_season = title("Frieren", 1);
+        final_season.final_season = true;
+        let mut movie = title("Frieren", 1);
+        movie.movie = true;
+
+        let learned = [
+            (title("Frieren", 1), "52991"),
+            (title("Frieren", 2), "59978"),
+            (part_2, "2"),
+            (final_season, "3"),
+            (movie, "4"),
+        ];
+        for (title, anime_id) in &learned {
+            let snapshot = mappings.insert(title, AnimeId((*anime_id).to_owned()));
+            snapshot.save().unwrap();
+        }
+
+        let loaded = LearnedMappings::load_from(path.clone());
+        fs::remove_file(&path).unwrap();
+
+        for (title, anime_id) in &learned {
+            assert_eq!(loaded.get(title), Some(&AnimeId((*anime_id).to_owned())));
+        }
+        // Looked up by the normalized title
+        assert_eq!(
+            loaded.get(&title("  FRIEREN ", 1)),
+            Some(&AnimeId("52991".to_owned()))
+        );
+        assert_eq!(loaded.get(&title("Frieren", 3)), None);
+        assert_eq!(loaded.get(&title("Sousou no Frieren", 1)), None);
+    }
+}
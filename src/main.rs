@@ -1,6 +1,6 @@
 use std::env;
 
-use clap::Command;
+use clap::{Arg, ArgAction, Command};
 use gettextrs::TextDomain;
 use log::info;
 
@@ -14,9 +14,15 @@ mod clients;
 mod config;
 mod constants;
 mod gtk_gui;
+mod learned_mappings;
 mod logging;
+mod matroska;
+mod offline_database;
 mod player_controller;
 mod title_recognizer;
+mod url_utils;
+mod watch_session;
+mod webhook_server;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -39,12 +45,35 @@ async fn main() -> anyhow::Result<()> {
                 .version(APP_VERSION)
                 .author(APP_AUTHORS),
         )
+        .subcommand(
+            Command::new("recognize")
+                .about("Show how a filename or title is recognized, without scrobbling it")
+                .version(APP_VERSION)
+                .author(APP_AUTHORS)
+                .arg(
+                    Arg::new("input")
+                        .help("Filename, path, URL or window title to recognize")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("resolve")
+                        .long("resolve")
+                        .help("Also find the matching MyAnimeList entry")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
         .get_matches();
 
-    let mut app = TundraApp::init()?;
+    let app = TundraApp::init()?;
 
     if matches.subcommand_matches("authenticate").is_some() {
         app.authenticate_mal_cli().await?;
+    } else if let Some(matches) = matches.subcommand_matches("recognize") {
+        let input = matches
+            .get_one::<String>("input")
+            .expect("input is required");
+        app.recognize_cli(input, matches.get_flag("resolve"))
+            .await?;
     } else if matches.subcommand_matches("daemon").is_some() {
         app.check_mal_authenticated();
         app.run_daemon().await;
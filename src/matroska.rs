@@ -0,0 +1,450 @@
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+const EBML_ID: u32 = 0x1A45_DFA3;
+const SEGMENT_ID: u32 = 0x1853_8067;
+const SEEK_HEAD_ID: u32 = 0x114D_9B74;
+const SEEK_ID: u32 = 0x4DBB;
+const SEEK_ID_ID: u32 = 0x53AB;
+const SEEK_POSITION_ID: u32 = 0x53AC;
+const INFO_ID: u32 = 0x1549_A966;
+const TITLE_ID: u32 = 0x7BA9;
+const TAGS_ID: u32 = 0x1254_C367;
+const TAG_ID: u32 = 0x7373;
+const TARGETS_ID: u32 = 0x63C0;
+const TARGET_TYPE_VALUE_ID: u32 = 0x68CA;
+const SIMPLE_TAG_ID: u32 = 0x67C8;
+const TAG_NAME_ID: u32 = 0x45A3;
+const TAG_STRING_ID: u32 = 0x4487;
+const CLUSTER_ID: u32 = 0x1F43_B675;
+
+/// Info and Tags elements are read into memory; anything larger than this is
+/// not a sane value for these elements.
+const MAX_METADATA_ELEMENT_SIZE: u64 = 4 * 1024 * 1024;
+
+/// Target type value of the whole series (`COLLECTION`).
+pub const TARGET_COLLECTION: u64 = 70;
+/// Target type value of a season (`SEASON`).
+pub const TARGET_SEASON: u64 = 60;
+/// Target type value of a single episode (`EPISODE`), which is also the
+/// default when a tag does not specify its target.
+pub const TARGET_EPISODE: u64 = 50;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MatroskaTag {
+    pub target_type_value: u64,
+    pub name: String,
+    pub value: String,
+}
+
+/// Title and tags embedded in a Matroska (MKV/WebM) container.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MatroskaTags {
+    pub segment_title: Option<String>,
+    pub tags: Vec<MatroskaTag>,
+}
+
+impl MatroskaTags {
+    pub fn read(path: &Path) -> io::Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        Self::read_from(&mut reader)
+    }
+
+    /// Reads the title and tags from given Matroska stream. Once the Segment
+    /// element is found, a damaged or truncated stream is not an error; the
+    /// metadata read until then is returned instead.
+    pub fn read_from<R: Read + Seek>(reader: &mut R) -> io::Result<Self> {
+        let (id, size) = read_element_header(reader)?;
+        if id != EBML_ID {
+            return Err(invalid_data("not an EBML file"));
+        }
+        skip(reader, size)?;
+
+        let (id, size) = read_element_header(reader)?;
+        if id != SEGMENT_ID {
+            return Err(invalid_data("Segment element not found"));
+        }
+        let segment_start = reader.stream_position()?;
+        let segment_end = size.and_then(|size| segment_start.checked_add(size));
+
+        let mut result = Self::default();
+        let mut info_found = false;
+        let mut tags_found = false;
+        let mut seek_positions = Vec::new();
+
+        while segment_end.is_none_or(|end| reader.stream_position().is_ok_and(|pos| pos < end)) {
+            let Ok((id, size)) = read_element_header(reader) else {
+                break;
+            };
+
+            match id {
+                SEEK_HEAD_ID | INFO_ID | TAGS_ID => {
+                    let Ok(data) = read_data(reader, size) else {
+                        break;
+                    };
+                    match id {
+                        SEEK_HEAD_ID => seek_positions.extend(parse_seek_head(&data)),
+                        INFO_ID => {
+                            result.segment_title = parse_info(&data);
+                            info_found = true;
+                        }
+                        _ => {
+                            result.tags.extend(parse_tags(&data));
+                            tags_found = true;
+                        }
+                    }
+                }
+                CLUSTER_ID => {
+                    // Tags are usually stored after the clusters; use the seek
+                    // head instead of walking through the whole file
+                    break;
+                }
+                _ => {
+                    if skip(reader, size).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+
+        for (id, position) in seek_positions {
+            let missing = match id {
+                INFO_ID => !info_found,
+                TAGS_ID => !tags_found,
+                _ => false,
+            };
+            if !missing {
+                continue;
+            }
+
+            let Some(data) = segment_start
+                .checked_add(position)
+                .and_then(|position| read_element_at(reader, position, id).ok())
+            else {
+                continue;
+            };
+            if id == INFO_ID {
+                result.segment_title = parse_info(&data);
+            } else {
+                result.tags.extend(parse_tags(&data));
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Returns the value of the first tag with given name and target type.
+    pub fn get(&self, target_type_value: u64, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|tag| tag.target_type_value == target_type_value && tag.name == name)
+            .map(|tag| tag.value.as_str())
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Reads an EBML variable-size integer. For element IDs, the length marker
+/// is kept as a part of the value, as per the specification.
+fn read_vint<R: Read>(reader: &mut R, keep_marker: bool) -> io::Result<(u64, usize)> {
+    let mut first = [0u8; 1];
+    reader.read_exact(&mut first)?;
+    let length = first[0].leading_zeros() as usize + 1;
+    if length > 8 {
+        return Err(invalid_data("invalid variable-size integer"));
+    }
+
+    let mut value = if keep_marker {
+        u64::from(first[0])
+    } else {
+        u64::from(first[0]) & (0xFF >> length)
+    };
+    let mut rest = [0u8; 7];
+    reader.read_exact(&mut rest[..length - 1])?;
+    for byte in &rest[..length - 1] {
+        value = (value << 8) | u64::from(*byte);
+    }
+
+    Ok((value, length))
+}
+
+/// Reads an element header and returns its ID and data size; `None` size
+/// means the size is unknown.
+fn read_element_header<R: Read>(reader: &mut R) -> io::Result<(u32, Option<u64>)> {
+    let (id, id_length) = read_vint(reader, true)?;
+    if id_length > 4 {
+        return Err(invalid_data("invalid element ID"));
+    }
+    let (size, size_length) = read_vint(reader, false)?;
+    let unknown_size = (1 << (7 * size_length)) - 1;
+    let size = if size == unknown_size {
+        None
+    } else {
+        Some(size)
+    };
+
+    Ok((id as u32, size))
+}
+
+/// Reads the data of the element at given position, failing if it is not
+/// an element with given ID.
+fn read_element_at<R: Read + Seek>(reader: &mut R, position: u64, id: u32) -> io::Result<Vec<u8>> {
+    reader.seek(SeekFrom::Start(position))?;
+    let (found_id, size) = read_element_header(reader)?;
+    if found_id != id {
+        return Err(invalid_data("unexpected element at the seek position"));
+    }
+
+    read_data(reader, size)
+}
+
+fn skip<R: Seek>(reader: &mut R, size: Option<u64>) -> io::Result<()> {
+    let size = size.ok_or_else(|| invalid_data("cannot skip an element of unknown size"))?;
+    let size = i64::try_from(size).map_err(|_| invalid_data("element is too large"))?;
+    reader.seek(SeekFrom::Current(size))?;
+    Ok(())
+}
+
+fn read_data<R: Read>(reader: &mut R, size: Option<u64>) -> io::Result<Vec<u8>> {
+    let size = size
+        .filter(|size| *size <= MAX_METADATA_ELEMENT_SIZE)
+        .ok_or_else(|| invalid_data("metadata element is too large"))?;
+    let mut data = vec![0; size as usize];
+    reader.read_exact(&mut data)?;
+    Ok(data)
+}
+
+/// Iterates over the child elements stored in `data`.
+fn children(data: &[u8]) -> impl Iterator<Item = (u32, &[u8])> {
+    let mut cursor = io::Cursor::new(data);
+    std::iter::from_fn(move || {
+        let (id, size) = read_element_header(&mut cursor).ok()?;
+        let start = cursor.position() as usize;
+        let end = start.checked_add(size? as usize)?;
+        let child = data.get(start..end)?;
+        cursor.set_position(end as u64);
+        Some((id, child))
+    })
+}
+
+fn parse_uint(data: &[u8]) -> u64 {
+    data.iter()
+        .take(8)
+        .fold(0, |value, byte| (value << 8) | u64::from(*byte))
+}
+
+fn parse_string(data: &[u8]) -> String {
+    String::from_utf8_lossy(data)
+        .trim_end_matches('\0')
+        .to_owned()
+}
+
+fn parse_seek_head(data: &[u8]) -> Vec<(u32, u64)> {
+    children(data)
+        .filter(|(id, _)| *id == SEEK_ID)
+        .filter_map(|(_, seek)| {
+            let mut seek_id = None;
+            let mut seek_position = None;
+            for (id, value) in children(seek) {
+                match id {
+                    SEEK_ID_ID => seek_id = Some(parse_uint(value) as u32),
+                    SEEK_POSITION_ID => seek_position = Some(parse_uint(value)),
+                    _ => {}
+                }
+            }
+            Some((seek_id?, seek_position?))
+        })
+        .collect()
+}
+
+fn parse_info(data: &[u8]) -> Option<String> {
+    children(data)
+        .find(|(id, _)| *id == TITLE_ID)
+        .map(|(_, title)| parse_string(title))
+        .filter(|title| !title.trim().is_empty())
+}
+
+fn parse_tags(data: &[u8]) -> Vec<MatroskaTag> {
+    let mut result = Vec::new();
+
+    for (_, tag) in children(data).filter(|(id, _)| *id == TAG_ID) {
+        let target_type_value = children(tag)
+            .find(|(id, _)| *id == TARGETS_ID)
+            .and_then(|(_, targets)| {
+                children(targets)
+                    .find(|(id, _)| *id == TARGET_TYPE_VALUE_ID)
+                    .map(|(_, value)| parse_uint(value))
+            })
+            .unwrap_or(TARGET_EPISODE);
+
+        for (_, simple_tag) in children(tag).filter(|(id, _)| *id == SIMPLE_TAG_ID) {
+            let mut name = None;
+            let mut value = None;
+            for (id, data) in children(simple_tag) {
+                match id {
+                    TAG_NAME_ID => name = Some(parse_string(data)),
+                    TAG_STRING_ID => value = Some(parse_string(data)),
+                    _ => {}
+                }
+            }
+
+            if let (Some(name), Some(value)) = (name, value) {
+                result.push(MatroskaTag {
+                    target_type_value,
+                    name: name.to_uppercase(),
+                    value,
+                });
+            }
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    /// Encodes an element with given ID and data, using an 8-byte size
+    fn element(id: u32, data: &[u8]) -> Vec<u8> {
+        let mut result: Vec<u8> = id
+            .to_be_bytes()
+            .into_iter()
+            .skip_while(|x| *x == 0)
+            .collect();
+        result.push(0x01);
+        result.extend_from_slice(&(data.len() as u64).to_be_bytes()[1..]);
+        result.extend_from_slice(data);
+        result
+    }
+
+    fn simple_tag(name: &str, value: &str) -> Vec<u8> {
+        let mut data = element(TAG_NAME_ID, name.as_bytes());
+        data.extend(element(TAG_STRING_ID, value.as_bytes()));
+        element(SIMPLE_TAG_ID, &data)
+    }
+
+    fn tags() -> Vec<u8> {
+        let mut tag = element(TARGETS_ID, &element(TARGET_TYPE_VALUE_ID, &[70]));
+        tag.extend(simple_tag("title", "Sousou no Frieren"));
+        element(TAGS_ID, &element(TAG_ID, &tag))
+    }
+
+    fn info() -> Vec<u8> {
+        element(INFO_ID, &element(TITLE_ID, b"Frieren - 05"))
+    }
+
+    fn seek_head(id: u32, position: u64) -> Vec<u8> {
+        let mut seek = element(SEEK_ID_ID, &id.to_be_bytes());
+        seek.extend(element(SEEK_POSITION_ID, &position.to_be_bytes()));
+        element(SEEK_HEAD_ID, &element(SEEK_ID, &seek))
+    }
+
+    fn file(segment: &[u8]) -> Vec<u8> {
+        let mut result = element(EBML_ID, &[]);
+        result.extend(element(SEGMENT_ID, segment));
+        result
+    }
+
+    fn read(data: Vec<u8>) -> io::Result<MatroskaTags> {
+        MatroskaTags::read_from(&mut Cursor::new(data))
+    }
+
+    fn expected_tags() -> Vec<MatroskaTag> {
+        vec![MatroskaTag {
+            target_type_value: TARGET_COLLECTION,
+            name: "TITLE".to_owned(),
+            value: "Sousou no Frieren".to_owned(),
+        }]
+    }
+
+    #[test]
+    fn reads_title_and_tags() {
+        let result = read(file(&[info(), tags()].concat())).unwrap();
+
+        assert_eq!(result.segment_title.as_deref(), Some("Frieren - 05"));
+        assert_eq!(result.tags, expected_tags());
+        assert_eq!(
+            result.get(TARGET_COLLECTION, "TITLE"),
+            Some("Sousou no Frieren")
+        );
+    }
+
+    #[test]
+    fn reads_tags_after_clusters_using_seek_head() {
+        let info = info();
+        let cluster = element(CLUSTER_ID, &[0; 16]);
+        // Positions are relative to the start of the segment data
+        let seek_head_length = seek_head(TAGS_ID, 0).len();
+        let tags_position = seek_head_length + info.len() + cluster.len();
+        let segment = [
+            seek_head(TAGS_ID, tags_position as u64),
+            info,
+            cluster,
+            tags(),
+        ]
+        .concat();
+
+        let result = read(file(&segment)).unwrap();
+
+        assert_eq!(result.segment_title.as_deref(), Some("Frieren - 05"));
+        assert_eq!(result.tags, expected_tags());
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert!(read(b"RIFF\0\0\0\0WAVE".to_vec()).is_err());
+        assert!(read(Vec::new()).is_err());
+    }
+
+    #[test]
+    fn returns_partial_result_of_truncated_file() {
+        let mut data = file(&[info(), tags()].concat());
+        data.truncate(data.len() - 5);
+
+        let result = read(data).unwrap();
+
+        assert_eq!(result.segment_title.as_deref(), Some("Frieren - 05"));
+        assert!(result.tags.is_empty());
+    }
+
+    #[test]
+    fn ignores_oversized_seek_position() {
+        let segment = [
+            info(),
+            seek_head(TAGS_ID, u64::MAX),
+            element(CLUSTER_ID, &[]),
+        ]
+        .concat();
+
+        let result = read(file(&segment)).unwrap();
+
+        assert_eq!(result.segment_title.as_deref(), Some("Frieren - 05"));
+        assert!(result.tags.is_empty());
+    }
+
+    #[test]
+    fn ignores_oversized_element() {
+        let mut segment = info();
+        // Void element claiming to be 2^56 - 2 bytes long
+        segment.extend([0xEC, 0x01, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFE]);
+
+        let result = read(file(&segment)).unwrap();
+        assert_eq!(result.segment_title.as_deref(), Some("Frieren - 05"));
+
+        let mut segment = info();
+        // Tags element too large to be read into memory
+        segment.extend([
+            0x12, 0x54, 0xC3, 0x67, 0x01, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+        ]);
+
+        let result = read(file(&segment)).unwrap();
+        assert_eq!(result.segment_title.as_deref(), Some("Frieren - 05"));
+        assert!(result.tags.is_empty());
+    }
+}
@@ -0,0 +1,399 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::Context;
+use log::{debug, info};
+use serde::Deserialize;
+
+use crate::anime_relations::AnimeDbs;
+use crate::title_recognizer::{Title, normalize_key};
+
+const MAL_SOURCE_PREFIX: &str = "https://myanimelist.net/anime/";
+const KITSU_SOURCE_PREFIX: &str = "https://kitsu.app/anime/";
+const ANILIST_SOURCE_PREFIX: &str = "https://anilist.co/anime/";
+
+#[derive(Debug, Deserialize)]
+struct DatabaseFile {
+    data: Vec<DatabaseEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DatabaseEntry {
+    sources: Vec<String>,
+    title: String,
+    #[serde(rename = "type")]
+    media_type: EntryType,
+    episodes: i32,
+    #[serde(default)]
+    synonyms: Vec<String>,
+    #[serde(default)]
+    related_anime: Vec<String>,
+    anime_season: Option<AnimeSeason>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "UPPERCASE")]
+enum EntryType {
+    Tv,
+    Movie,
+    Ova,
+    Ona,
+    Special,
+    #[serde(other)]
+    Unknown,
+}
+
+impl EntryType {
+    fn is_series(self) -> bool {
+        matches!(self, EntryType::Tv | EntryType::Ona)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AnimeSeason {
+    season: String,
+    year: Option<i32>,
+}
+
+impl AnimeSeason {
+    fn sort_key(&self) -> (i32, i32) {
+        let season = match self.season.as_str() {
+            "WINTER" => 0,
+            "SPRING" => 1,
+            "SUMMER" => 2,
+            "FALL" => 3,
+            _ => 4,
+        };
+        (self.year.unwrap_or(i32::MAX), season)
+    }
+}
+
+struct Entry {
+    title: String,
+    media_type: EntryType,
+    episodes: i32,
+    ids: HashMap<AnimeDbs, i64>,
+    related: Vec<usize>,
+    season: (i32, i32),
+}
+
+/// A title resolved using the offline database.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LocalMatch {
+    pub mal_id: i64,
+    pub title: String,
+    pub episodes: i32,
+}
+
+/// Local copy of an [anime-offline-database](https://github.com/manami-project/anime-offline-database)
+/// dump, used to resolve titles without searching MAL.
+#[derive(Default)]
+pub struct OfflineDatabase {
+    entries: Vec<Entry>,
+    by_title: HashMap<String, Vec<usize>>,
+}
+
+impl OfflineDatabase {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let json = fs::read_to_string(path).with_context(|| {
+            format!("Could not read the offline database at {}", path.display())
+        })?;
+        let database_file: DatabaseFile =
+            serde_json::from_str(&json).context("Could not parse the offline database")?;
+
+        let database = Self::build(database_file.data);
+        info!(
+            "Loaded offline database with {} entries from {}",
+            database.entries.len(),
+            path.display()
+        );
+
+        Ok(database)
+    }
+
+    fn build(data: Vec<DatabaseEntry>) -> Self {
+        let mut by_source = HashMap::new();
+        for (index, entry) in data.iter().enumerate() {
+            for source in &entry.sources {
+                by_source.insert(source.as_str(), index);
+            }
+        }
+
+        let mut by_title: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut entries = Vec::with_capacity(data.len());
+        for (index, entry) in data.iter().enumerate() {
+            for title in std::iter::once(&entry.title).chain(&entry.synonyms) {
+                let indices = by_title.entry(normalize_key(title)).or_default();
+                if !indices.contains(&index) {
+                    indices.push(index);
+                }
+            }
+
+            entries.push(Entry {
+                title: entry.title.clone(),
+                media_type: entry.media_type,
+                episodes: entry.episodes,
+                ids: Self::parse_ids(&entry.sources),
+                related: entry
+                    .related_anime
+                    .iter()
+                    .filter_map(|x| by_source.get(x.as_str()).copied())
+                    .collect(),
+                season: entry
+                    .anime_season
+                    .as_ref()
+                    .map_or((i32::MAX, 4), AnimeSeason::sort_key),
+            });
+        }
+
+        Self { entries, by_title }
+    }
+
+    fn parse_ids(sources: &[String]) -> HashMap<AnimeDbs, i64> {
+        let prefixes = [
+            (AnimeDbs::Mal, MAL_SOURCE_PREFIX),
+            (AnimeDbs::Kitsu, KITSU_SOURCE_PREFIX),
+            (AnimeDbs::AniList, ANILIST_SOURCE_PREFIX),
+        ];
+
+        prefixes
+            .into_iter()
+            .filter_map(|(db, prefix)| {
+                let id = sources
+                    .iter()
+                    .find_map(|x| x.strip_prefix(prefix))
+                    .and_then(|x| i64::from_str(x).ok())?;
+                Some((db, id))
+            })
+            .collect()
+    }
+
+    /// Resolves given title to a MAL entry. Returns `None` if the title is
+    /// not known or is ambiguous (e.g. a remake sharing its name with the
+    /// original), so that the caller can fall back to searching MAL.
+    pub fn resolve(&self, title: &Title) -> Option<LocalMatch> {
+        let key = title.normalized_key();
+        let candidates = self.by_title.get(&key)?;
+
+        if title.movie {
+            return self.resolve_movie(title, candidates);
+        }
+
+        let rank = |index: &usize| {
+            let entry = &self.entries[*index];
+            (
+                normalize_key(&entry.title) == key,
+                entry.media_type.is_series(),
+            )
+        };
+        let best_rank = candidates.iter().map(rank).max()?;
+        let best: Vec<usize> = candidates
+            .iter()
+            .copied()
+            .filter(|x| rank(x) == best_rank)
+            .collect();
+
+        // Only keep the first seasons if the title matched several seasons of
+        // the same show
+        let roots: Vec<usize> = best
+            .iter()
+            .copied()
+            .filter(|x| {
+                !best.iter().any(|y| {
+                    self.entries[*x].related.contains(y)
+                        && self.entries[*y].season < self.entries[*x].season
+                })
+            })
+            .collect();
+        if roots.len() != 1 {
+            debug!("Offline database match for {} is ambiguous", title.title);
+            return None;
+        }
+
+        if title.final_season {
+            debug!("Final seasons cannot be resolved with the offline database");
+            return None;
+        }
+        let index = self.nth_season(roots[0], title.season_number + title.part_number - 1)?;
+        self.local_match(index)
+    }
+
+    /// Movies are not a part of the sequel chain, so the title has to match
+    /// exactly one entry that is not a series.
+    fn resolve_movie(&self, title: &Title, candidates: &[usize]) -> Option<LocalMatch> {
+        let movies: Vec<usize> = candidates
+            .iter()
+            .copied()
+            .filter(|x| !self.entries[*x].media_type.is_series())
+            .collect();
+        let [index] = movies[..] else {
+            debug!(
+                "No unambiguous offline database match for movie {}",
+                title.title
+            );
+            return None;
+        };
+
+        self.local_match(index)
+    }
+
+    fn local_match(&self, index: usize) -> Option<LocalMatch> {
+        let entry = &self.entries[index];
+        let mal_id = *entry.ids.get(&AnimeDbs::Mal)?;
+        debug!(
+            "Found {} (MAL ID {mal_id}) in the offline database",
+            entry.title
+        );
+
+        Some(LocalMatch {
+            mal_id,
+            title: entry.title.clone(),
+            episodes: entry.episodes,
+        })
+    }
+
+    /// Returns the n-th entry of the series. Each part of a split season is a
+    /// separate entry, just as on MAL.
+    fn nth_season(&self, index: usize, season_number: i32) -> Option<usize> {
+        let mut current = index;
+        for _ in 1..season_number {
+            current = self.sequel(current)?;
+        }
+
+        Some(current)
+    }
+
+    /// The database does not store relation types, so the sequel is the
+    /// earliest related series that aired after the given one.
+    fn sequel(&self, index: usize) -> Option<usize> {
+        let entry = &self.entries[index];
+
+        entry
+            .related
+            .iter()
+            .copied()
+            .filter(|x| {
+                let related = &self.entries[*x];
+                related.media_type.is_series() && related.season > entry.season
+            })
+            .min_by_key(|x| self.entries[*x].season)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::title_recognizer::TitleRecognizer;
+
+    fn entry(
+        id: i64,
+        title: &str,
+        media_type: &str,
+        season: (&str, i32),
+        related: &[i64],
+    ) -> serde_json::Value {
+        json!({
+            "sources": [format!("{MAL_SOURCE_PREFIX}{id}")],
+            "title": title,
+            "type": media_type,
+            "episodes": 12,
+            "synonyms": [],
+            "relatedAnime": related
+                .iter()
+                .map(|x| format!("{MAL_SOURCE_PREFIX}{x}"))
+                .collect::<Vec<_>>(),
+            "animeSeason": {"season": season.0, "year": season.1},
+        })
+    }
+
+    /// Shingeki no Kyojin with its sequels (listed out of order, as in the
+    /// dump) and a movie, plus two unrelated shows sharing a title
+    fn database() -> OfflineDatabase {
+        let data = json!({
+            "data": [
+                entry(38524, "Shingeki no Kyojin Season 3 Part 2", "TV", ("SPRING", 2019), &[35760, 40028]),
+                entry(16498, "Shingeki no Kyojin", "TV", ("SPRING", 2013), &[25777, 23775]),
+                entry(35760, "Shingeki no Kyojin Season 3", "TV", ("SUMMER", 2018), &[25777, 38524]),
+                entry(25777, "Shingeki no Kyojin Season 2", "TV", ("SPRING", 2017), &[16498, 35760]),
+                entry(23775, "Shingeki no Kyojin Movie 1", "MOVIE", ("FALL", 2014), &[16498]),
+                entry(40028, "Shingeki no Kyojin: The Final Season", "TV", ("WINTER", 2020), &[38524]),
+                entry(1, "Hunter x Hunter", "TV", ("FALL", 1999), &[]),
+                entry(11061, "Hunter x Hunter", "TV", ("FALL", 2011), &[]),
+            ]
+        });
+
+        OfflineDatabase::build(serde_json::from_value::<DatabaseFile>(data).unwrap().data)
+    }
+
+    fn resolve(title: &str, season_number: i32, part_number: i32) -> Option<i64> {
+        let mut title = Title::new(title.to_owned(), season_number, 1);
+        title.part_number = part_number;
+
+        database().resolve(&title).map(|x| x.mal_id)
+    }
+
+    #[test]
+    fn resolves_sequels() {
+        assert_eq!(resolve("Shingeki no Kyojin", 1, 1), Some(16498));
+        assert_eq!(resolve("Shingeki no Kyojin", 2, 1), Some(25777));
+        assert_eq!(resolve("Shingeki no Kyojin", 3, 1), Some(35760));
+        // Each part of a split season is a separate entry
+        assert_eq!(resolve("Shingeki no Kyojin", 3, 2), Some(38524));
+        assert_eq!(resolve("Shingeki no Kyojin", 5, 1), Some(40028));
+        assert_eq!(resolve("Shingeki no Kyojin", 6, 1), None);
+    }
+
+    #[test]
+    fn resolves_exact_titles_of_sequels() {
+        assert_eq!(resolve("Shingeki no Kyojin Season 2", 1, 1), Some(25777));
+        assert_eq!(resolve("shingeki no kyojin: season 3", 1, 1), Some(35760));
+    }
+
+    #[test]
+    fn resolves_season_suffixes() {
+        for (input, mal_id) in [
+            ("Shingeki no Kyojin 2nd Season", 25777),
+            ("Shingeki no Kyojin II", 25777),
+            ("Shingeki no Kyojin Season 3 Part 2", 38524),
+        ] {
+            let title = TitleRecognizer::post_process(Title::new(input.to_owned(), 1, 1));
+
+            assert_eq!(
+                database().resolve(&title).map(|x| x.mal_id),
+                Some(mal_id),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn does_not_resolve_final_season() {
+        let mut title = Title::new("Shingeki no Kyojin".to_owned(), 1, 1);
+        title.final_season = true;
+
+        assert_eq!(database().resolve(&title), None);
+    }
+
+    #[test]
+    fn resolves_movies() {
+        let mut title = Title::new("Shingeki no Kyojin Movie 1".to_owned(), 1, 3);
+        title.movie = true;
+        assert_eq!(database().resolve(&title).map(|x| x.mal_id), Some(23775));
+
+        // The series is not a movie, so MAL has to be searched instead
+        let mut title = Title::new("Shingeki no Kyojin".to_owned(), 1, 1);
+        title.movie = true;
+        assert_eq!(database().resolve(&title), None);
+    }
+
+    #[test]
+    fn does_not_resolve_ambiguous_or_unknown_titles() {
+        assert_eq!(resolve("Hunter x Hunter", 1, 1), None);
+        assert_eq!(resolve("Frieren", 1, 1), None);
+    }
+}
@@ -0,0 +1,199 @@
+use std::time::Duration;
+
+use dbus::Message;
+use dbus::message::{MatchRule, SignalArgs};
+use dbus::nonblock::stdintf::org_freedesktop_dbus::PropertiesPropertiesChanged;
+use dbus::nonblock::{MsgMatch, SyncConnection};
+use log::{debug, warn};
+use tokio::sync::mpsc;
+use tokio::time;
+
+use crate::player_controller::PlayerControllerResult;
+use crate::player_controller::mpris::OrgMprisMediaPlayer2PlayerSeeked;
+
+const MPRIS_NAME_PREFIX: &str = "org.mpris.MediaPlayer2.";
+const MPRIS_PATH: &str = "/org/mpris/MediaPlayer2";
+const MPRIS_PLAYER_INTERFACE: &str = "org.mpris.MediaPlayer2.Player";
+/// Player properties that affect what is being scrobbled
+const WATCHED_PROPERTIES: [&str; 3] = ["PlaybackStatus", "Metadata", "Rate"];
+
+const EVENT_CHANNEL_SIZE: usize = 32;
+/// Events arriving within this time after the first one are handled together,
+/// as players usually emit several of them when switching tracks
+const DEBOUNCE_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Change in the state of MPRIS players.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PlayerEvent {
+    /// A player with given bus name has appeared on the bus.
+    PlayerAdded(String),
+    /// A player with given bus name has disappeared from the bus.
+    PlayerRemoved(String),
+    /// Playback status or the track of the player with given unique bus name
+    /// has changed.
+    PropertiesChanged(String),
+    /// The player with given unique bus name has seeked to given position.
+    Seeked(String, Duration),
+}
+
+/// Stream of [`PlayerEvent`]s received from the session bus.
+pub struct PlayerEvents {
+    receiver: Option<mpsc::Receiver<PlayerEvent>>,
+    /// Whether some of the players do not emit any events
+    polling_required: bool,
+    /// The callbacks are only called as long as the matches are alive
+    _matches: Vec<MsgMatch>,
+}
+
+impl PlayerEvents {
+    /// Subscribes to the player events. If that is not possible, no events
+    /// are ever received, so the callers just fall back to polling.
+    pub(super) async fn subscribe(connection: &SyncConnection) -> Self {
+        match Self::add_matches(connection).await {
+            Ok((receiver, matches)) => Self {
+                receiver: Some(receiver),
+                polling_required: false,
+                _matches: matches,
+            },
+            Err(e) => {
+                warn!("Could not subscribe to player events, falling back to polling: {e}");
+                Self::polling()
+            }
+        }
+    }
+
+    /// Returns a stream that never receives any events, so that the players
+    /// are always polled.
+    pub fn polling() -> Self {
+        Self {
+            receiver: None,
+            polling_required: true,
+            _matches: Vec::new(),
+        }
+    }
+
+    /// Marks that there are players which do not emit any events.
+    pub(super) fn set_polling_required(&mut self) {
+        self.polling_required = true;
+    }
+
+    /// Returns whether the players need to be polled, because the events are
+    /// not received from all of them.
+    pub fn is_polling_required(&self) -> bool {
+        self.polling_required || self.receiver.is_none()
+    }
+
+    async fn add_matches(
+        connection: &SyncConnection,
+    ) -> PlayerControllerResult<(mpsc::Receiver<PlayerEvent>, Vec<MsgMatch>)> {
+        let (tx, rx) = mpsc::channel(EVENT_CHANNEL_SIZE);
+
+        let sender = tx.clone();
+        let name_owner_changed = connection
+            .add_match(MatchRule::new_signal(
+                "org.freedesktop.DBus",
+                "NameOwnerChanged",
+            ))
+            .await?
+            .cb(move |_, (name, _, new_owner): (String, String, String)| {
+                if !name.starts_with(MPRIS_NAME_PREFIX) {
+                    return true;
+                }
+
+                let event = if new_owner.is_empty() {
+                    PlayerEvent::PlayerRemoved(name)
+                } else {
+                    PlayerEvent::PlayerAdded(name)
+                };
+                Self::send(&sender, event)
+            });
+
+        let sender = tx.clone();
+        let properties_changed = connection
+            .add_match(
+                MatchRule::new_signal(
+                    PropertiesPropertiesChanged::INTERFACE,
+                    PropertiesPropertiesChanged::NAME,
+                )
+                .with_path(MPRIS_PATH),
+            )
+            .await?
+            .cb(move |message, changed: PropertiesPropertiesChanged| {
+                let relevant = changed.interface_name == MPRIS_PLAYER_INTERFACE
+                    && changed
+                        .changed_properties
+                        .keys()
+                        .chain(&changed.invalidated_properties)
+                        .any(|x| WATCHED_PROPERTIES.contains(&x.as_str()));
+                if !relevant {
+                    return true;
+                }
+
+                Self::send(
+                    &sender,
+                    PlayerEvent::PropertiesChanged(Self::sender_name(&message)),
+                )
+            });
+
+        let sender = tx;
+        let seeked = connection
+            .add_match(
+                MatchRule::new_signal(
+                    OrgMprisMediaPlayer2PlayerSeeked::INTERFACE,
+                    OrgMprisMediaPlayer2PlayerSeeked::NAME,
+                )
+                .with_path(MPRIS_PATH),
+            )
+            .await?
+            .cb(move |message, seeked: OrgMprisMediaPlayer2PlayerSeeked| {
+                let position = Duration::from_micros(seeked.position.max(0) as u64);
+                Self::send(
+                    &sender,
+                    PlayerEvent::Seeked(Self::sender_name(&message), position),
+                )
+            });
+
+        Ok((rx, vec![name_owner_changed, properties_changed, seeked]))
+    }
+
+    /// Queues the event, unless the queue is full, in which case the players
+    /// are going to be checked anyway. Returns `false` once nobody listens
+    /// to the events anymore.
+    fn send(sender: &mpsc::Sender<PlayerEvent>, event: PlayerEvent) -> bool {
+        match sender.try_send(event) {
+            Ok(()) | Err(mpsc::error::TrySendError::Full(_)) => true,
+            Err(mpsc::error::TrySendError::Closed(_)) => false,
+        }
+    }
+
+    fn sender_name(message: &Message) -> String {
+        message.sender().map(|x| x.to_string()).unwrap_or_default()
+    }
+
+    /// Waits at most `timeout` for the next player event. Returns `None` if
+    /// no event has arrived in that time.
+    pub async fn wait(&mut self, timeout: Duration) -> Option<PlayerEvent> {
+        let Some(receiver) = &mut self.receiver else {
+            time::sleep(timeout).await;
+            return None;
+        };
+
+        let event = match time::timeout(timeout, receiver.recv()).await {
+            Ok(Some(event)) => event,
+            Ok(None) => {
+                warn!("Player events are not received anymore, falling back to polling");
+                self.receiver = None;
+                return None;
+            }
+            Err(_) => return None,
+        };
+        debug!("Player event: {event:?}");
+
+        time::sleep(DEBOUNCE_INTERVAL).await;
+        while let Ok(event) = receiver.try_recv() {
+            debug!("Player event: {event:?}");
+        }
+
+        Some(event)
+    }
+}
@@ -0,0 +1,410 @@
+use std::time::Duration;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use log::debug;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use url::Url;
+
+use crate::config::KodiConfig;
+use crate::player_controller::{MediaPlayer, PlayerControllerResult, filename_from_url};
+use crate::title_recognizer::Title;
+
+const PLAYER_NAME: &str = "Kodi";
+const DESKTOP_ENTRY: &str = "kodi";
+
+/// Kodi controlled through its JSON-RPC interface over HTTP, e.g. running on
+/// another machine in the LAN.
+pub struct KodiPlayer {
+    id: String,
+    item: KodiItem,
+    properties: KodiProperties,
+}
+
+impl KodiPlayer {
+    /// Connects to Kodi if it is configured. Returns `None` if Kodi is not
+    /// running or is not playing any video.
+    pub(super) async fn connect(client: &reqwest::Client, config: &KodiConfig) -> Option<Self> {
+        let url = config.url.as_deref()?;
+
+        match Self::get_state(client, config).await {
+            Ok(Some((item, properties))) => Some(Self {
+                id: format!("kodi:{url}"),
+                item,
+                properties,
+            }),
+            Ok(None) => None,
+            Err(e) => {
+                debug!("Could not connect to Kodi at {url}: {e}");
+                None
+            }
+        }
+    }
+
+    async fn get_state(
+        client: &reqwest::Client,
+        config: &KodiConfig,
+    ) -> PlayerControllerResult<Option<(KodiItem, KodiProperties)>> {
+        let players: Vec<KodiActivePlayer> =
+            Self::call(client, config, "Player.GetActivePlayers", json!({})).await?;
+        let Some(player) = players.into_iter().find(|x| x.r#type == "video") else {
+            return Ok(None);
+        };
+
+        let item: KodiItemResult = Self::call(
+            client,
+            config,
+            "Player.GetItem",
+            json!({
+                "playerid": player.playerid,
+                "properties": ["showtitle", "season", "episode", "file", "title"],
+            }),
+        )
+        .await?;
+        let properties = Self::call(
+            client,
+            config,
+            "Player.GetProperties",
+            json!({
+                "playerid": player.playerid,
+                "properties": ["time", "totaltime", "speed"],
+            }),
+        )
+        .await?;
+
+        Ok(Some((item.item, properties)))
+    }
+
+    async fn call<T: DeserializeOwned>(
+        client: &reqwest::Client,
+        config: &KodiConfig,
+        method: &str,
+        params: Value,
+    ) -> PlayerControllerResult<T> {
+        let url = config.url.as_deref().unwrap_or_default();
+        let url = format!("{}/jsonrpc", url.trim_end_matches('/'));
+        let request = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+
+        let mut request = client.post(url).json(&request);
+        if let Some(password) = &config.password {
+            request = request.basic_auth(&config.username, Some(password));
+        }
+        let response: KodiResponse<T> = request.send().await?.error_for_status()?.json().await?;
+
+        match (response.result, response.error) {
+            (Some(result), _) => Ok(result),
+            (None, Some(error)) => Err(anyhow!("Kodi {method} call failed: {}", error.message)),
+            (None, None) => Err(anyhow!("Kodi {method} call returned no result")),
+        }
+    }
+}
+
+#[async_trait]
+impl MediaPlayer for KodiPlayer {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    async fn player_name(&self) -> PlayerControllerResult<String> {
+        Ok(PLAYER_NAME.to_owned())
+    }
+
+    async fn desktop_entry(&self) -> PlayerControllerResult<String> {
+        Ok(DESKTOP_ENTRY.to_owned())
+    }
+
+    async fn is_currently_playing(&self) -> PlayerControllerResult<bool> {
+        Ok(self.properties.speed != 0.0)
+    }
+
+    /// Returns the title from the Kodi library, so that it does not need to
+    /// be recognized. Items that are not in the library and specials (season
+    /// 0), which are numbered differently than on MAL, are recognized from
+    /// the filename as usual.
+    async fn title(&self) -> PlayerControllerResult<Option<Title>> {
+        let item = &self.item;
+
+        let title = match item.r#type.as_str() {
+            "episode" if !item.showtitle.is_empty() && item.season > 0 && item.episode > 0 => {
+                Title::new(item.showtitle.clone(), item.season, item.episode)
+            }
+            "movie" if !item.title.is_empty() => {
+                let mut title = Title::new(item.title.clone(), 1, 1);
+                title.movie = true;
+                title
+            }
+            _ => return Ok(None),
+        };
+
+        Ok(Some(title))
+    }
+
+    async fn title_played(&self) -> PlayerControllerResult<String> {
+        [&self.item.title, &self.item.label]
+            .into_iter()
+            .find(|x| !x.is_empty())
+            .cloned()
+            .ok_or(anyhow!("Title was not found"))
+    }
+
+    /// Returns the URL of the played file; Kodi reports local files as plain
+    /// paths and network shares as URLs (e.g. `smb://`).
+    async fn url_played(&self) -> PlayerControllerResult<String> {
+        let file = &self.item.file;
+        if file.is_empty() {
+            return Err(anyhow!("URL was not found"));
+        }
+
+        if file.starts_with('/') {
+            Url::from_file_path(file)
+                .map(String::from)
+                .map_err(|_| anyhow!("Could not convert {file} to an URL"))
+        } else {
+            Ok(file.clone())
+        }
+    }
+
+    /// Falls back to the title if the URL does not contain a meaningful
+    /// filename, e.g. for streams played by add-ons.
+    async fn filename_played(&self) -> PlayerControllerResult<String> {
+        match filename_from_url(&self.item.file) {
+            Some(filename) => Ok(filename),
+            None => self.title_played().await,
+        }
+    }
+
+    async fn duration(&self) -> PlayerControllerResult<Duration> {
+        let duration = self.properties.totaltime.to_duration();
+        if duration.is_zero() {
+            return Err(anyhow!("duration was not found"));
+        }
+
+        Ok(duration)
+    }
+
+    async fn position(&self) -> PlayerControllerResult<Duration> {
+        Ok(self.properties.time.to_duration())
+    }
+
+    async fn rate(&self) -> PlayerControllerResult<f64> {
+        Ok(self.properties.speed)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct KodiResponse<T> {
+    result: Option<T>,
+    error: Option<KodiError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct KodiError {
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct KodiActivePlayer {
+    playerid: i64,
+    /// "video", "audio" or "picture"
+    r#type: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct KodiItemResult {
+    item: KodiItem,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct KodiItem {
+    /// "episode", "movie", or "unknown" for files outside of the library
+    r#type: String,
+    label: String,
+    title: String,
+    showtitle: String,
+    season: i32,
+    episode: i32,
+    file: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct KodiProperties {
+    /// 0 when paused
+    speed: f64,
+    time: KodiTime,
+    totaltime: KodiTime,
+}
+
+#[derive(Debug, Deserialize)]
+struct KodiTime {
+    hours: u64,
+    minutes: u64,
+    seconds: u64,
+    milliseconds: u64,
+}
+
+impl KodiTime {
+    fn to_duration(&self) -> Duration {
+        Duration::from_secs(self.hours * 3600 + self.minutes * 60 + self.seconds)
+            + Duration::from_millis(self.milliseconds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_player(item: Value, properties: Value) -> KodiPlayer {
+        let item: KodiResponse<KodiItemResult> =
+            serde_json::from_value(json!({"id": 1, "jsonrpc": "2.0", "result": {"item": item}}))
+                .unwrap();
+        let properties: KodiResponse<KodiProperties> =
+            serde_json::from_value(json!({"id": 1, "jsonrpc": "2.0", "result": properties}))
+                .unwrap();
+
+        KodiPlayer {
+            id: "kodi:http://localhost:8080".to_owned(),
+            item: item.result.unwrap().item,
+            properties: properties.result.unwrap(),
+        }
+    }
+
+    fn time(hours: u64, minutes: u64, seconds: u64, milliseconds: u64) -> Value {
+        json!({
+            "hours": hours,
+            "minutes": minutes,
+            "seconds": seconds,
+            "milliseconds": milliseconds,
+        })
+    }
+
+    fn playing() -> Value {
+        json!({
+            "speed": 1,
+            "time": time(0, 5, 30, 250),
+            "totaltime": time(0, 23, 40, 0),
+        })
+    }
+
+    #[tokio::test]
+    async fn reads_episodes() {
+        let player = make_player(
+            json!({
+                "id": 42,
+                "type": "episode",
+                "label": "Phantoms of the Dead",
+                "title": "Phantoms of the Dead",
+                "showtitle": "Frieren: Beyond Journey's End",
+                "season": 1,
+                "episode": 5,
+                "file": "/media/anime/Frieren/Season 01/Frieren - S01E05.mkv",
+            }),
+            playing(),
+        );
+
+        assert_eq!(
+            player.title().await.unwrap(),
+            Some(Title::new("Frieren: Beyond Journey's End".to_owned(), 1, 5))
+        );
+        assert_eq!(
+            player.url_played().await.unwrap(),
+            "file:///media/anime/Frieren/Season%2001/Frieren%20-%20S01E05.mkv"
+        );
+        assert_eq!(
+            player.filename_played().await.unwrap(),
+            "Frieren - S01E05.mkv"
+        );
+    }
+
+    #[tokio::test]
+    async fn reads_movies() {
+        let player = make_player(
+            json!({
+                "id": 7,
+                "type": "movie",
+                "label": "Your Name.",
+                "title": "Your Name.",
+                "showtitle": "",
+                "season": -1,
+                "episode": -1,
+                "file": "smb://nas/movies/Kimi no Na wa. (2016).mkv",
+            }),
+            playing(),
+        );
+
+        let mut title = Title::new("Your Name.".to_owned(), 1, 1);
+        title.movie = true;
+        assert_eq!(player.title().await.unwrap(), Some(title));
+        assert_eq!(
+            player.url_played().await.unwrap(),
+            "smb://nas/movies/Kimi no Na wa. (2016).mkv"
+        );
+    }
+
+    #[tokio::test]
+    async fn recognizes_items_without_a_show_from_the_filename() {
+        let player = make_player(
+            json!({
+                "type": "unknown",
+                "label": "[SubsPlease] Sousou no Frieren - 05 (1080p).mkv",
+                "title": "",
+                "showtitle": "",
+                "season": -1,
+                "episode": -1,
+                "file": "/downloads/[SubsPlease] Sousou no Frieren - 05 (1080p).mkv",
+            }),
+            playing(),
+        );
+        assert_eq!(player.title().await.unwrap(), None);
+        assert_eq!(
+            player.title_played().await.unwrap(),
+            "[SubsPlease] Sousou no Frieren - 05 (1080p).mkv"
+        );
+
+        // Specials are numbered differently than on MAL
+        let player = make_player(
+            json!({
+                "type": "episode",
+                "title": "Recap",
+                "showtitle": "Frieren: Beyond Journey's End",
+                "season": 0,
+                "episode": 1,
+            }),
+            playing(),
+        );
+        assert_eq!(player.title().await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn converts_times() {
+        let player = make_player(json!({"type": "unknown"}), playing());
+
+        assert!(player.is_currently_playing().await.unwrap());
+        assert_eq!(
+            player.position().await.unwrap(),
+            Duration::from_millis(330_250)
+        );
+        assert_eq!(
+            player.duration().await.unwrap(),
+            Duration::from_secs(23 * 60 + 40)
+        );
+        assert_eq!(player.rate().await.unwrap(), 1.0);
+
+        let player = make_player(
+            json!({"type": "unknown"}),
+            json!({
+                "speed": 0,
+                "time": time(1, 2, 3, 4),
+                "totaltime": time(0, 0, 0, 0),
+            }),
+        );
+        assert!(!player.is_currently_playing().await.unwrap());
+        assert_eq!(
+            player.position().await.unwrap(),
+            Duration::from_millis(3_723_004)
+        );
+        assert!(player.duration().await.is_err());
+    }
+}
@@ -0,0 +1,188 @@
+use std::path::Path;
+
+use url::Url;
+
+/// Query parameters that streaming servers commonly use to pass the name of
+/// the streamed file
+const TITLE_QUERY_PARAMS: &[&str] = &["filename", "file", "title", "name", "dn"];
+/// Extensions of playlists and web pages, which do not say anything about the
+/// played media
+const MEANINGLESS_EXTENSIONS: &[&str] = &[
+    "m3u", "m3u8", "mpd", "ism", "isml", "php", "asp", "aspx", "jsp", "htm", "html", "json",
+];
+/// Names used by streaming servers and websites regardless of the media
+const MEANINGLESS_STEMS: &[&str] = &[
+    "index",
+    "master",
+    "playlist",
+    "manifest",
+    "chunklist",
+    "stream",
+    "video",
+    "media",
+    "play",
+    "watch",
+    "embed",
+    "download",
+    "file",
+];
+/// Hexadecimal names at least this long are most likely hashes or IDs
+const MIN_HASH_LENGTH: usize = 16;
+
+/// Returns the name of the file at given URL (`file://`, `smb://`, `sftp://`,
+/// `http(s)://`...) or local path, or `None` if it does not have a name that
+/// could describe the media, e.g. for HLS playlists or websites.
+pub fn filename_from_url(url: &str) -> Option<String> {
+    let filename = match Url::parse(url) {
+        // Single letter schemes are Windows drive letters
+        Ok(url) if url.scheme().len() > 1 => filename_from_parsed_url(&url)?,
+        _ => path_filename(Path::new(url))?,
+    };
+
+    is_meaningful_filename(&filename).then_some(filename)
+}
+
+fn filename_from_parsed_url(url: &Url) -> Option<String> {
+    if url.scheme() == "file" {
+        return path_filename(&url.to_file_path().ok()?);
+    }
+
+    let query_filename = url
+        .query_pairs()
+        .find(|(key, value)| {
+            TITLE_QUERY_PARAMS.contains(&key.to_ascii_lowercase().as_str())
+                && !value.trim().is_empty()
+        })
+        .and_then(|(_, value)| last_path_component(value.trim()));
+    if query_filename.is_some() {
+        return query_filename;
+    }
+
+    let segment = url.path_segments()?.rfind(|x| !x.is_empty())?;
+    Some(
+        percent_encoding::percent_decode_str(segment)
+            .decode_utf8_lossy()
+            .into_owned(),
+    )
+}
+
+/// Query values are sometimes whole paths (e.g. `?file=/anime/Show/01.mkv`),
+/// of which only the filename is interesting.
+fn last_path_component(value: &str) -> Option<String> {
+    value
+        .rsplit(['/', '\\'])
+        .map(str::trim)
+        .find(|x| !x.is_empty())
+        .map(str::to_owned)
+}
+
+fn path_filename(path: &Path) -> Option<String> {
+    Some(path.file_name()?.to_str()?.to_owned())
+}
+
+fn is_meaningful_filename(filename: &str) -> bool {
+    let path = Path::new(filename);
+    let Some(stem) = path.file_stem().and_then(|x| x.to_str()) else {
+        return false;
+    };
+    let stem = stem.trim().to_ascii_lowercase();
+    let extension = path
+        .extension()
+        .and_then(|x| x.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+
+    let is_id = stem.chars().all(|x| x.is_ascii_digit())
+        || (stem.len() >= MIN_HASH_LENGTH
+            && stem.chars().all(|x| x.is_ascii_hexdigit() || x == '-'));
+
+    !stem.is_empty()
+        && !is_id
+        && !MEANINGLESS_EXTENSIONS.contains(&extension.as_str())
+        && !MEANINGLESS_STEMS.contains(&stem.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn returns_filenames_of_files() {
+        let cases = [
+            (
+                "file:///home/user/Anime/Sousou%20no%20Frieren%20-%2005.mkv",
+                "Sousou no Frieren - 05.mkv",
+            ),
+            (
+                "/home/user/Anime/Sousou no Frieren - 05.mkv",
+                "Sousou no Frieren - 05.mkv",
+            ),
+            (
+                "smb://nas/anime/Sousou%20no%20Frieren/%5BGroup%5D%20Sousou%20no%20Frieren%20-%2005.mkv",
+                "[Group] Sousou no Frieren - 05.mkv",
+            ),
+            (
+                "sftp://user@server:22/srv/anime/Sousou%20no%20Frieren%20-%2005.mkv",
+                "Sousou no Frieren - 05.mkv",
+            ),
+            (
+                "https://example.com/videos/Sousou%20no%20Frieren%20-%2005.mp4/",
+                "Sousou no Frieren - 05.mp4",
+            ),
+        ];
+
+        for (url, expected) in cases {
+            assert_eq!(filename_from_url(url).as_deref(), Some(expected), "{url}");
+        }
+    }
+
+    #[test]
+    fn returns_filenames_from_query() {
+        let cases = [
+            (
+                "https://example.com/stream?id=123&filename=Sousou%20no%20Frieren%20-%2005.mkv",
+                "Sousou no Frieren - 05.mkv",
+            ),
+            (
+                "https://example.com/download.php?file=/anime/Sousou%20no%20Frieren/Sousou%20no%20Frieren%20-%2005.mkv",
+                "Sousou no Frieren - 05.mkv",
+            ),
+            (
+                "https://example.com/get?name=anime%5CSousou%20no%20Frieren%20-%2005.mkv",
+                "Sousou no Frieren - 05.mkv",
+            ),
+            (
+                "magnet:?xt=urn:btih:0123&dn=Sousou+no+Frieren+-+05.mkv",
+                "Sousou no Frieren - 05.mkv",
+            ),
+        ];
+
+        for (url, expected) in cases {
+            assert_eq!(filename_from_url(url).as_deref(), Some(expected), "{url}");
+        }
+    }
+
+    #[test]
+    fn skips_meaningless_filenames() {
+        for url in [
+            "https://cdn.example.com/hls/abc/index.m3u8?token=xyz",
+            "https://cdn.example.com/dash/manifest.mpd",
+            "https://example.com/watch.php?v=1",
+            "https://example.com/embed/",
+            "https://example.com/videos/1234567",
+            "https://example.com/videos/0123456789abcdef0123456789abcdef.mp4",
+            "https://example.com/videos/3f2504e0-4f89-11d3-9a0c-0305e82c3301",
+            "https://example.com/stream?file=%2F",
+            "https://example.com/",
+        ] {
+            assert_eq!(filename_from_url(url), None, "{url}");
+        }
+    }
+
+    #[test]
+    fn keeps_short_hexadecimal_names() {
+        assert!(is_meaningful_filename("Bad Apple.mkv"));
+        assert!(is_meaningful_filename("ef.mkv"));
+        assert!(!is_meaningful_filename("deadbeefdeadbeef.mkv"));
+    }
+}
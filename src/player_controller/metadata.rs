@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+
+use dbus::arg::{RefArg, Variant};
+
+/// A single value of the MPRIS metadata map.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MetadataValue {
+    String(String),
+    Strings(Vec<String>),
+    Integer(i64),
+    Float(f64),
+}
+
+impl MetadataValue {
+    fn from_ref_arg(value: &dyn RefArg) -> Option<Self> {
+        if let Some(value) = value.as_str() {
+            Some(Self::String(value.to_owned()))
+        } else if let Some(values) = value.as_iter() {
+            Some(Self::Strings(
+                values
+                    .filter_map(|x| x.as_str().map(str::to_owned))
+                    .collect(),
+            ))
+        } else if let Some(value) = value.as_i64() {
+            Some(Self::Integer(value))
+        } else if let Some(value) = value.as_u64() {
+            Some(Self::Integer(value as i64))
+        } else {
+            value.as_f64().map(Self::Float)
+        }
+    }
+}
+
+/// Metadata of the track being played, as published in the MPRIS `Metadata`
+/// property (`xesam:title`, `xesam:url`, `xesam:album`, `mpris:length`...).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Metadata(HashMap<String, MetadataValue>);
+
+impl Metadata {
+    pub fn from_dbus(metadata: &HashMap<String, Variant<Box<dyn RefArg + 'static>>>) -> Self {
+        Self(
+            metadata
+                .iter()
+                .filter_map(|(key, value)| {
+                    MetadataValue::from_ref_arg(&value.0).map(|value| (key.clone(), value))
+                })
+                .collect(),
+        )
+    }
+
+    pub fn get(&self, key: &str) -> Option<&MetadataValue> {
+        self.0.get(key)
+    }
+
+    /// Returns the value as a string; for string lists (such as
+    /// `xesam:artist`), the first element is returned.
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        match self.get(key)? {
+            MetadataValue::String(value) => Some(value),
+            MetadataValue::Strings(values) => values.first().map(String::as_str),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as an integer; some players publish numbers as
+    /// floats or strings, so these are converted as well.
+    pub fn get_integer(&self, key: &str) -> Option<i64> {
+        match self.get(key)? {
+            MetadataValue::Integer(value) => Some(*value),
+            MetadataValue::Float(value) => Some(*value as i64),
+            MetadataValue::String(value) => value.trim().parse().ok(),
+            MetadataValue::Strings(_) => None,
+        }
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.0.keys().map(String::as_str)
+    }
+}
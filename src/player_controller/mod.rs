@@ -1,121 +1,189 @@
-use std::path::Path;
+use std::sync::{Arc, RwLock};
 use std::time::Duration;
 
-use anyhow::anyhow;
-use dbus::blocking::{Connection, Proxy};
-use mpris::{OrgMprisMediaPlayer2, OrgMprisMediaPlayer2Player};
-
+use async_trait::async_trait;
+use dbus::nonblock::{Proxy, SyncConnection};
+pub use events::PlayerEvents;
+pub use kodi_player::KodiPlayer;
+use log::error;
+pub use media_url::filename_from_url;
+pub use metadata::Metadata;
+pub use mpris_player::MprisPlayer;
+pub use mpv_player::MpvPlayer;
+pub use remote_player::{RemotePlayback, RemotePlaybacks};
+pub use vlc_player::VlcPlayer;
+
+use crate::config::Config;
+use crate::constants::USER_AGENT;
+use crate::title_recognizer::Title;
+
+mod events;
+mod kodi_player;
+mod media_url;
+mod metadata;
 mod mpris;
+mod mpris_player;
+mod mpv_player;
+mod remote_player;
+mod vlc_player;
 
 type PlayerControllerResult<T> = anyhow::Result<T>;
 
+/// Timeout of a single D-Bus call
+const DBUS_TIMEOUT: Duration = Duration::from_millis(2000);
+/// Timeout of a single request to the HTTP interfaces of the players
+const HTTP_TIMEOUT: Duration = Duration::from_millis(2000);
+
 pub struct PlayerController {
-    connection: Connection,
+    config: Arc<RwLock<Config>>,
+    connection: Arc<SyncConnection>,
+    http_client: reqwest::Client,
+    remote_playbacks: Arc<RemotePlaybacks>,
 }
 
 impl PlayerController {
-    pub fn new() -> PlayerControllerResult<Self> {
-        let connection = Connection::new_session()?;
-        Ok(Self { connection })
+    /// Connects to the session bus. Needs to be called from within a Tokio
+    /// runtime, which drives the connection.
+    pub fn new(config: Arc<RwLock<Config>>) -> PlayerControllerResult<Self> {
+        let (resource, connection) = dbus_tokio::connection::new_session_sync()?;
+        tokio::spawn(async move {
+            let error = resource.await;
+            error!("Lost connection to the D-Bus session bus: {error}");
+        });
+
+        let http_client = reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .timeout(HTTP_TIMEOUT)
+            .build()?;
+
+        Ok(Self {
+            config,
+            connection,
+            http_client,
+            remote_playbacks: Arc::new(RemotePlaybacks::default()),
+        })
     }
+}
 
-    pub fn get_players(&self) -> anyhow::Result<Vec<Player<'_>>> {
-        let proxy =
-            self.connection
-                .with_proxy("org.freedesktop.DBus", "/", Duration::from_millis(5000));
-        let (names,): (Vec<String>,) =
-            proxy.method_call("org.freedesktop.DBus", "ListNames", ())?;
+/// Source of the players to check, so that they can be replaced in tests.
+#[async_trait]
+pub trait PlayerSource: Send + Sync {
+    /// Returns the players that are currently running.
+    async fn get_players(&self) -> anyhow::Result<Vec<Box<dyn MediaPlayer>>>;
 
-        Ok(names
-            .iter()
-            .filter(|x| x.starts_with("org.mpris.MediaPlayer2"))
-            .map(|x| Player::new(self, x.to_owned()))
-            .collect())
-    }
-}
+    /// Subscribes to the events of all players.
+    async fn events(&self) -> PlayerEvents;
 
-pub struct Player<'a> {
-    dbus_proxy: Proxy<'a, &'a Connection>,
+    /// Returns the playbacks on other devices, to be updated by the webhooks
+    /// of the media servers.
+    fn remote_playbacks(&self) -> Arc<RemotePlaybacks>;
 }
 
-impl<'a> Player<'a> {
-    fn new(player_controller: &'a PlayerController, name: String) -> Self {
-        Self {
-            dbus_proxy: player_controller.connection.with_proxy(
-                name,
-                "/org/mpris/MediaPlayer2",
-                Duration::from_millis(5000),
-            ),
+#[async_trait]
+impl PlayerSource for PlayerController {
+    /// Returns the MPRIS players on the session bus, followed by the
+    /// configured players that are controlled directly and are running, and
+    /// the playbacks reported by the webhooks.
+    async fn get_players(&self) -> anyhow::Result<Vec<Box<dyn MediaPlayer>>> {
+        let mpv_config = self.config.read().unwrap().mpv.clone();
+        let vlc_config = self.config.read().unwrap().vlc.clone();
+        let kodi_config = self.config.read().unwrap().kodi.clone();
+
+        let proxy = Proxy::new(
+            "org.freedesktop.DBus",
+            "/",
+            DBUS_TIMEOUT,
+            self.connection.clone(),
+        );
+        let (names,): (Vec<String>,) = proxy
+            .method_call("org.freedesktop.DBus", "ListNames", ())
+            .await?;
+
+        let mut players: Vec<Box<dyn MediaPlayer>> = names
+            .into_iter()
+            .filter(|x| x.starts_with("org.mpris.MediaPlayer2"))
+            .map(|x| Box::new(MprisPlayer::new(self.connection.clone(), x)) as _)
+            .collect();
+        for mpv_player in MpvPlayer::connect_all(&mpv_config).await {
+            players.push(Box::new(mpv_player));
+        }
+        if let Some(vlc_player) = VlcPlayer::connect(&self.http_client, &vlc_config).await {
+            players.push(Box::new(vlc_player));
+        }
+        if let Some(kodi_player) = KodiPlayer::connect(&self.http_client, &kodi_config).await {
+            players.push(Box::new(kodi_player));
         }
+        for remote_player in self.remote_playbacks.players() {
+            players.push(Box::new(remote_player));
+        }
+
+        Ok(players)
     }
 
-    pub fn player_name(&self) -> PlayerControllerResult<String> {
-        Ok(self.dbus_proxy.identity()?)
+    /// The players controlled directly and the webhooks do not emit any
+    /// events, so if these are configured, the players still need to be
+    /// polled.
+    async fn events(&self) -> PlayerEvents {
+        let mut events = PlayerEvents::subscribe(&self.connection).await;
+        let config = self.config.read().unwrap();
+        if config.mpv.is_enabled()
+            || config.vlc.is_enabled()
+            || config.kodi.is_enabled()
+            || config.webhook.is_enabled()
+        {
+            events.set_polling_required();
+        }
+
+        events
     }
 
-    pub fn is_currently_playing(&self) -> PlayerControllerResult<bool> {
-        Ok(self.dbus_proxy.playback_status()? == "Playing")
+    fn remote_playbacks(&self) -> Arc<RemotePlaybacks> {
+        self.remote_playbacks.clone()
     }
+}
+
+/// A media player, either found on the session bus or connected to directly.
+#[async_trait]
+pub trait MediaPlayer: Send + Sync {
+    /// Returns the identifier of the player, unique among all the players
+    /// that are currently running.
+    fn id(&self) -> &str;
+
+    async fn player_name(&self) -> PlayerControllerResult<String>;
+
+    async fn desktop_entry(&self) -> PlayerControllerResult<String>;
+
+    async fn is_currently_playing(&self) -> PlayerControllerResult<bool>;
 
-    pub fn title_played(&self) -> PlayerControllerResult<String> {
-        let metadata = self.dbus_proxy.metadata()?;
-        let title_value = metadata
-            .get("xesam:title")
-            .ok_or(anyhow!("Title was not found"))?;
-        let title: &str = title_value
-            .0
-            .as_str()
-            .ok_or(anyhow!("Title is not string"))?;
-
-        Ok(title.to_owned())
+    /// Returns whether the player has reported the media as watched, so that
+    /// it should be scrobbled regardless of the time it has been watched for.
+    async fn is_watched(&self) -> PlayerControllerResult<bool> {
+        Ok(false)
     }
 
-    pub fn filename_played(&self) -> PlayerControllerResult<String> {
-        let metadata = self.dbus_proxy.metadata()?;
-        let url_value = metadata
-            .get("xesam:url")
-            .ok_or(anyhow!("URL was not found"))?;
-        let url: &str = url_value.0.as_str().ok_or(anyhow!("URL is not string"))?;
-        let url: String = if url.starts_with("file://") {
-            percent_encoding::percent_decode_str(url)
-                .decode_utf8()?
-                .to_string()
-        } else {
-            url.to_owned()
-        };
-        let url = url.replace("file://", "");
-        let path = Path::new(&url);
-
-        Ok(path
-            .file_name()
-            .ok_or(anyhow!("URL does not have a filename"))?
-            .to_str()
-            .ok_or(anyhow!("filename is not string"))?
-            .to_owned())
+    async fn metadata(&self) -> PlayerControllerResult<Metadata> {
+        Ok(Metadata::default())
     }
 
-    pub fn duration(&self) -> PlayerControllerResult<Duration> {
-        let metadata = self.dbus_proxy.metadata()?;
-        let x = metadata
-            .get("mpris:length")
-            .ok_or(anyhow!("duration was not found"))?;
-        let duration_any = x.0.as_any();
-
-        if let Some(duration) = duration_any.downcast_ref::<i64>() {
-            Ok(Duration::from_micros(*duration as u64))
-        } else if let Some(duration) = duration_any.downcast_ref::<u64>() {
-            Ok(Duration::from_micros(*duration))
-        } else if let Some(duration) = duration_any.downcast_ref::<f64>() {
-            Ok(Duration::from_micros(*duration as u64))
-        } else {
-            Err(anyhow!("duration is not integer"))
-        }
+    /// Returns the title if the player knows exactly what is being played
+    /// (e.g. from its media library), so that it does not need to be
+    /// recognized.
+    async fn title(&self) -> PlayerControllerResult<Option<Title>> {
+        Ok(None)
     }
 
-    pub fn position(&self) -> PlayerControllerResult<f32> {
-        let position = Duration::from_micros(self.dbus_proxy.position()? as u64);
-        let duration = self.duration()?;
+    async fn title_played(&self) -> PlayerControllerResult<String>;
+
+    async fn url_played(&self) -> PlayerControllerResult<String>;
+
+    async fn filename_played(&self) -> PlayerControllerResult<String>;
+
+    async fn duration(&self) -> PlayerControllerResult<Duration>;
+
+    async fn position(&self) -> PlayerControllerResult<Duration>;
 
-        Ok(position.as_secs_f32() / duration.as_secs_f32())
+    /// Returns the playback rate, e.g. 1.5 when playing at 1.5× speed.
+    async fn rate(&self) -> PlayerControllerResult<f64> {
+        Ok(1.0)
     }
 }
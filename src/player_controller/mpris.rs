@@ -1,42 +1,42 @@
-use dbus::{arg, blocking};
+use dbus::{arg, nonblock};
 
 #[allow(dead_code)]
 pub trait OrgMprisMediaPlayer2 {
-    fn identity(&self) -> Result<String, dbus::Error>;
-    fn desktop_entry(&self) -> Result<String, dbus::Error>;
-    fn supported_uri_schemes(&self) -> Result<Vec<String>, dbus::Error>;
-    fn supported_mime_types(&self) -> Result<Vec<String>, dbus::Error>;
+    fn identity(&self) -> nonblock::MethodReply<String>;
+    fn desktop_entry(&self) -> nonblock::MethodReply<String>;
+    fn supported_uri_schemes(&self) -> nonblock::MethodReply<Vec<String>>;
+    fn supported_mime_types(&self) -> nonblock::MethodReply<Vec<String>>;
 }
 
-impl<C: ::std::ops::Deref<Target = blocking::Connection>> OrgMprisMediaPlayer2
-    for blocking::Proxy<'_, C>
+impl<T: nonblock::NonblockReply, C: ::std::ops::Deref<Target = T>> OrgMprisMediaPlayer2
+    for nonblock::Proxy<'_, C>
 {
-    fn identity(&self) -> Result<String, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+    fn identity(&self) -> nonblock::MethodReply<String> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(
             self,
             "org.mpris.MediaPlayer2",
             "Identity",
         )
     }
 
-    fn desktop_entry(&self) -> Result<String, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+    fn desktop_entry(&self) -> nonblock::MethodReply<String> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(
             self,
             "org.mpris.MediaPlayer2",
             "DesktopEntry",
         )
     }
 
-    fn supported_uri_schemes(&self) -> Result<Vec<String>, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+    fn supported_uri_schemes(&self) -> nonblock::MethodReply<Vec<String>> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(
             self,
             "org.mpris.MediaPlayer2",
             "SupportedUriSchemes",
         )
     }
 
-    fn supported_mime_types(&self) -> Result<Vec<String>, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+    fn supported_mime_types(&self) -> nonblock::MethodReply<Vec<String>> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(
             self,
             "org.mpris.MediaPlayer2",
             "SupportedMimeTypes",
@@ -45,21 +45,21 @@ impl<C: ::std::ops::Deref<Target = blocking::Connection>> OrgMprisMediaPlayer2
 }
 
 pub trait OrgMprisMediaPlayer2Player {
-    fn playback_status(&self) -> Result<String, dbus::Error>;
+    fn playback_status(&self) -> nonblock::MethodReply<String>;
     fn metadata(
         &self,
-    ) -> Result<
+    ) -> nonblock::MethodReply<
         ::std::collections::HashMap<String, arg::Variant<Box<dyn arg::RefArg + 'static>>>,
-        dbus::Error,
     >;
-    fn position(&self) -> Result<i64, dbus::Error>;
+    fn position(&self) -> nonblock::MethodReply<i64>;
+    fn rate(&self) -> nonblock::MethodReply<f64>;
 }
 
-impl<C: ::std::ops::Deref<Target = blocking::Connection>> OrgMprisMediaPlayer2Player
-    for blocking::Proxy<'_, C>
+impl<T: nonblock::NonblockReply, C: ::std::ops::Deref<Target = T>> OrgMprisMediaPlayer2Player
+    for nonblock::Proxy<'_, C>
 {
-    fn playback_status(&self) -> Result<String, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+    fn playback_status(&self) -> nonblock::MethodReply<String> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(
             self,
             "org.mpris.MediaPlayer2.Player",
             "PlaybackStatus",
@@ -68,22 +68,53 @@ impl<C: ::std::ops::Deref<Target = blocking::Connection>> OrgMprisMediaPlayer2Pl
 
     fn metadata(
         &self,
-    ) -> Result<
+    ) -> nonblock::MethodReply<
         ::std::collections::HashMap<String, arg::Variant<Box<dyn arg::RefArg + 'static>>>,
-        dbus::Error,
     > {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(
             self,
             "org.mpris.MediaPlayer2.Player",
             "Metadata",
         )
     }
 
-    fn position(&self) -> Result<i64, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+    fn position(&self) -> nonblock::MethodReply<i64> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(
             self,
             "org.mpris.MediaPlayer2.Player",
             "Position",
         )
     }
+
+    fn rate(&self) -> nonblock::MethodReply<f64> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(
+            self,
+            "org.mpris.MediaPlayer2.Player",
+            "Rate",
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct OrgMprisMediaPlayer2PlayerSeeked {
+    pub position: i64,
+}
+
+impl arg::AppendAll for OrgMprisMediaPlayer2PlayerSeeked {
+    fn append(&self, i: &mut arg::IterAppend) {
+        arg::RefArg::append(&self.position, i);
+    }
+}
+
+impl arg::ReadAll for OrgMprisMediaPlayer2PlayerSeeked {
+    fn read(i: &mut arg::Iter) -> Result<Self, arg::TypeMismatchError> {
+        Ok(OrgMprisMediaPlayer2PlayerSeeked {
+            position: i.read()?,
+        })
+    }
+}
+
+impl dbus::message::SignalArgs for OrgMprisMediaPlayer2PlayerSeeked {
+    const INTERFACE: &'static str = "org.mpris.MediaPlayer2.Player";
+    const NAME: &'static str = "Seeked";
 }
@@ -0,0 +1,114 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use dbus::nonblock::{Proxy, SyncConnection};
+use tokio::sync::OnceCell;
+
+use crate::player_controller::mpris::{OrgMprisMediaPlayer2, OrgMprisMediaPlayer2Player};
+use crate::player_controller::{
+    DBUS_TIMEOUT, MediaPlayer, Metadata, PlayerControllerResult, filename_from_url,
+};
+
+/// A player found on the session bus. A new instance is created for each
+/// check of the players, so the metadata is only fetched once per check.
+pub struct MprisPlayer {
+    bus_name: String,
+    dbus_proxy: Proxy<'static, Arc<SyncConnection>>,
+    metadata: OnceCell<Metadata>,
+}
+
+impl MprisPlayer {
+    pub(super) fn new(connection: Arc<SyncConnection>, bus_name: String) -> Self {
+        Self {
+            dbus_proxy: Proxy::new(
+                bus_name.clone(),
+                "/org/mpris/MediaPlayer2",
+                DBUS_TIMEOUT,
+                connection,
+            ),
+            bus_name,
+            metadata: OnceCell::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl MediaPlayer for MprisPlayer {
+    fn id(&self) -> &str {
+        &self.bus_name
+    }
+
+    async fn player_name(&self) -> PlayerControllerResult<String> {
+        Ok(self.dbus_proxy.identity().await?)
+    }
+
+    async fn desktop_entry(&self) -> PlayerControllerResult<String> {
+        Ok(self.dbus_proxy.desktop_entry().await?)
+    }
+
+    async fn is_currently_playing(&self) -> PlayerControllerResult<bool> {
+        Ok(self.dbus_proxy.playback_status().await? == "Playing")
+    }
+
+    async fn metadata(&self) -> PlayerControllerResult<Metadata> {
+        let metadata = self
+            .metadata
+            .get_or_try_init(|| async {
+                let metadata = self.dbus_proxy.metadata().await?;
+                anyhow::Ok(Metadata::from_dbus(&metadata))
+            })
+            .await?;
+
+        Ok(metadata.clone())
+    }
+
+    async fn title_played(&self) -> PlayerControllerResult<String> {
+        let metadata = self.metadata().await?;
+        let title = metadata
+            .get_str("xesam:title")
+            .ok_or(anyhow!("Title was not found"))?;
+
+        Ok(title.to_owned())
+    }
+
+    async fn url_played(&self) -> PlayerControllerResult<String> {
+        let metadata = self.metadata().await?;
+        let url = metadata
+            .get_str("xesam:url")
+            .ok_or(anyhow!("URL was not found"))?;
+
+        Ok(url.to_owned())
+    }
+
+    /// Falls back to the title if the URL does not contain a meaningful
+    /// filename, e.g. when streaming.
+    async fn filename_played(&self) -> PlayerControllerResult<String> {
+        let url = self.url_played().await?;
+
+        match filename_from_url(&url) {
+            Some(filename) => Ok(filename),
+            None => self.title_played().await,
+        }
+    }
+
+    async fn duration(&self) -> PlayerControllerResult<Duration> {
+        let metadata = self.metadata().await?;
+        let duration = metadata
+            .get_integer("mpris:length")
+            .ok_or(anyhow!("duration was not found"))?;
+
+        Ok(Duration::from_micros(duration as u64))
+    }
+
+    async fn position(&self) -> PlayerControllerResult<Duration> {
+        Ok(Duration::from_micros(
+            self.dbus_proxy.position().await?.max(0) as u64,
+        ))
+    }
+
+    async fn rate(&self) -> PlayerControllerResult<f64> {
+        Ok(self.dbus_proxy.rate().await?)
+    }
+}
@@ -0,0 +1,362 @@
+use std::fs;
+use std::os::unix::fs::FileTypeExt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, anyhow, bail};
+use async_trait::async_trait;
+use log::debug;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::UnixStream;
+use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::sync::Mutex;
+use url::Url;
+
+use crate::config::MpvConfig;
+use crate::player_controller::{MediaPlayer, PlayerControllerResult, filename_from_url};
+
+const PLAYER_NAME: &str = "mpv";
+const DESKTOP_ENTRY: &str = "mpv";
+
+/// mpv instance controlled through its JSON IPC socket (`--input-ipc-server`),
+/// for when the mpv-mpris plugin is not installed.
+pub struct MpvPlayer {
+    id: String,
+    connection: Mutex<MpvConnection>,
+}
+
+impl MpvPlayer {
+    /// Connects to all the configured sockets. Sockets nobody listens on
+    /// anymore (mpv does not remove them when it crashes) are skipped.
+    pub(super) async fn connect_all(config: &MpvConfig) -> Vec<Self> {
+        let mut players = Vec::new();
+
+        for path in Self::socket_paths(config) {
+            match MpvConnection::connect(&path).await {
+                Ok(connection) => players.push(Self {
+                    id: format!("mpv-ipc:{}", path.display()),
+                    connection: Mutex::new(connection),
+                }),
+                Err(e) => debug!("Could not connect to mpv socket {}: {e}", path.display()),
+            }
+        }
+
+        players
+    }
+
+    fn socket_paths(config: &MpvConfig) -> Vec<PathBuf> {
+        let mut paths = config.sockets.clone();
+
+        for dir in &config.socket_dirs {
+            let entries = match fs::read_dir(dir) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    debug!("Could not list mpv sockets in {}: {e}", dir.display());
+                    continue;
+                }
+            };
+            paths.extend(
+                entries
+                    .filter_map(Result::ok)
+                    .filter(|x| x.file_type().is_ok_and(|x| x.is_socket()))
+                    .map(|x| x.path()),
+            );
+        }
+
+        paths.sort();
+        paths.dedup();
+        paths
+    }
+
+    async fn property<T: DeserializeOwned>(&self, name: &str) -> PlayerControllerResult<Option<T>> {
+        self.connection.lock().await.get_property(name).await
+    }
+
+    async fn required_property<T: DeserializeOwned>(
+        &self,
+        name: &str,
+    ) -> PlayerControllerResult<T> {
+        self.property(name)
+            .await?
+            .ok_or_else(|| anyhow!("mpv property {name} is not available"))
+    }
+}
+
+#[async_trait]
+impl MediaPlayer for MpvPlayer {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    async fn player_name(&self) -> PlayerControllerResult<String> {
+        Ok(PLAYER_NAME.to_owned())
+    }
+
+    async fn desktop_entry(&self) -> PlayerControllerResult<String> {
+        Ok(DESKTOP_ENTRY.to_owned())
+    }
+
+    async fn is_currently_playing(&self) -> PlayerControllerResult<bool> {
+        // The path is not available while mpv is idle
+        if self.property::<String>("path").await?.is_none() {
+            return Ok(false);
+        }
+
+        Ok(self.property::<bool>("pause").await? == Some(false))
+    }
+
+    async fn title_played(&self) -> PlayerControllerResult<String> {
+        self.required_property("media-title").await
+    }
+
+    async fn url_played(&self) -> PlayerControllerResult<String> {
+        let path: String = self.required_property("path").await?;
+        if let Ok(url) = Url::parse(&path)
+            && url.scheme().len() > 1
+        {
+            return Ok(path);
+        }
+
+        // Local files are given as passed on the command line, possibly
+        // relative to the working directory of mpv
+        let mut full_path = PathBuf::from(&path);
+        if full_path.is_relative() {
+            let working_directory: String = self.required_property("working-directory").await?;
+            full_path = Path::new(&working_directory).join(full_path);
+        }
+
+        Url::from_file_path(&full_path)
+            .map(String::from)
+            .map_err(|_| anyhow!("Could not convert {path} to an URL"))
+    }
+
+    /// Falls back to the title if the URL does not contain a meaningful
+    /// filename, e.g. when streaming.
+    async fn filename_played(&self) -> PlayerControllerResult<String> {
+        let url = self.url_played().await?;
+
+        match filename_from_url(&url) {
+            Some(filename) => Ok(filename),
+            None => self.title_played().await,
+        }
+    }
+
+    async fn duration(&self) -> PlayerControllerResult<Duration> {
+        let duration: f64 = self.required_property("duration").await?;
+
+        Ok(Duration::try_from_secs_f64(duration)?)
+    }
+
+    async fn position(&self) -> PlayerControllerResult<Duration> {
+        let position: f64 = self.required_property("time-pos").await?;
+
+        Ok(Duration::try_from_secs_f64(position.max(0.0))?)
+    }
+
+    async fn rate(&self) -> PlayerControllerResult<f64> {
+        self.required_property("speed").await
+    }
+}
+
+/// Response to a command, or an event if it has no request ID.
+#[derive(Debug, Deserialize)]
+struct MpvResponse {
+    request_id: Option<u64>,
+    error: Option<String>,
+    #[serde(default)]
+    data: Value,
+}
+
+struct MpvConnection {
+    reader: BufReader<OwnedReadHalf>,
+    writer: OwnedWriteHalf,
+    last_request_id: u64,
+}
+
+impl MpvConnection {
+    async fn connect(path: &Path) -> PlayerControllerResult<Self> {
+        let (reader, writer) = UnixStream::connect(path).await?.into_split();
+
+        Ok(Self {
+            reader: BufReader::new(reader),
+            writer,
+            last_request_id: 0,
+        })
+    }
+
+    /// Returns the value of given property, or `None` if it is not available
+    /// at the moment (e.g. the duration while nothing is being played).
+    async fn get_property<T: DeserializeOwned>(
+        &mut self,
+        name: &str,
+    ) -> PlayerControllerResult<Option<T>> {
+        self.last_request_id += 1;
+        let request_id = self.last_request_id;
+        let request = json!({ "command": ["get_property", name], "request_id": request_id });
+        self.writer
+            .write_all(format!("{request}\n").as_bytes())
+            .await?;
+
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if self.reader.read_line(&mut line).await? == 0 {
+                bail!("mpv has closed the connection");
+            }
+
+            let response: MpvResponse =
+                serde_json::from_str(&line).context("Invalid response from mpv")?;
+            // Events can arrive before the response
+            if response.request_id != Some(request_id) {
+                continue;
+            }
+
+            return match response.error.as_deref() {
+                Some("success") => Ok(Some(serde_json::from_value(response.data)?)),
+                Some("property unavailable") => Ok(None),
+                error => Err(anyhow!(
+                    "Could not get mpv property {name}: {}",
+                    error.unwrap_or("unknown error")
+                )),
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::process;
+
+    use tokio::net::UnixListener;
+
+    use super::*;
+
+    /// Listens on a socket in a new temporary directory and answers
+    /// `get_property` commands with given properties, sending an event and a
+    /// response to another request before each response, as mpv can.
+    struct FakeMpv {
+        dir: PathBuf,
+        socket: PathBuf,
+    }
+
+    impl FakeMpv {
+        fn start(name: &str, properties: &[(&str, Value)]) -> Self {
+            let dir = std::env::temp_dir().join(format!("tundra-mpv-{}-{name}", process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            let socket = dir.join("mpv.sock");
+            let listener = UnixListener::bind(&socket).unwrap();
+
+            let properties: HashMap<String, Value> = properties
+                .iter()
+                .map(|(name, value)| ((*name).to_owned(), value.clone()))
+                .collect();
+            tokio::spawn(async move {
+                let (stream, _) = listener.accept().await.unwrap();
+                let (reader, mut writer) = stream.into_split();
+                let mut lines = BufReader::new(reader).lines();
+
+                while let Some(line) = lines.next_line().await.unwrap() {
+                    let request: Value = serde_json::from_str(&line).unwrap();
+                    let request_id = request["request_id"].as_u64().unwrap();
+                    let name = request["command"][1].as_str().unwrap();
+                    let response = match properties.get(name) {
+                        Some(value) => {
+                            json!({"request_id": request_id, "error": "success", "data": value})
+                        }
+                        None => json!({"request_id": request_id, "error": "property unavailable"}),
+                    };
+                    let messages = [
+                        json!({"event": "playback-restart"}),
+                        json!({"request_id": request_id + 1000, "error": "success", "data": 0}),
+                        response,
+                    ];
+
+                    for message in messages {
+                        writer
+                            .write_all(format!("{message}\n").as_bytes())
+                            .await
+                            .unwrap();
+                    }
+                }
+            });
+
+            Self { dir, socket }
+        }
+
+        async fn connect(&self) -> MpvConnection {
+            MpvConnection::connect(&self.socket).await.unwrap()
+        }
+
+        async fn player(&self) -> MpvPlayer {
+            MpvPlayer {
+                id: "mpv-test".to_owned(),
+                connection: Mutex::new(self.connect().await),
+            }
+        }
+    }
+
+    impl Drop for FakeMpv {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    #[tokio::test]
+    async fn gets_properties_skipping_events_and_other_responses() {
+        let mpv = FakeMpv::start(
+            "properties",
+            &[
+                ("media-title", json!("Show - 01")),
+                ("time-pos", json!(12.5)),
+            ],
+        );
+        let mut connection = mpv.connect().await;
+
+        let title: Option<String> = connection.get_property("media-title").await.unwrap();
+        let position: Option<f64> = connection.get_property("time-pos").await.unwrap();
+
+        assert_eq!(title.as_deref(), Some("Show - 01"));
+        assert_eq!(position, Some(12.5));
+    }
+
+    #[tokio::test]
+    async fn returns_none_for_unavailable_properties() {
+        let mpv = FakeMpv::start("unavailable", &[]);
+        let mut connection = mpv.connect().await;
+
+        let duration: Option<f64> = connection.get_property("duration").await.unwrap();
+
+        assert_eq!(duration, None);
+    }
+
+    #[tokio::test]
+    async fn resolves_relative_paths_against_working_directory() {
+        let mpv = FakeMpv::start(
+            "relative",
+            &[
+                ("path", json!("Show/Show - 01.mkv")),
+                ("working-directory", json!("/home/user/Videos")),
+            ],
+        );
+        let player = mpv.player().await;
+
+        assert_eq!(
+            player.url_played().await.unwrap(),
+            "file:///home/user/Videos/Show/Show%20-%2001.mkv"
+        );
+        assert_eq!(player.filename_played().await.unwrap(), "Show - 01.mkv");
+    }
+
+    #[tokio::test]
+    async fn keeps_urls() {
+        let url = "https://example.com/Show%20-%2001.mkv";
+        let mpv = FakeMpv::start("url", &[("path", json!(url))]);
+
+        assert_eq!(mpv.player().await.url_played().await.unwrap(), url);
+    }
+}
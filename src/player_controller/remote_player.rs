@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+
+use crate::player_controller::{MediaPlayer, PlayerControllerResult};
+use crate::title_recognizer::Title;
+
+/// Playbacks reported as watched are kept for this long, so that they are
+/// scrobbled even if the scrobbling fails at first
+const WATCHED_PLAYBACK_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+/// Playbacks are forgotten if nothing is reported about them for this long,
+/// e.g. when the client has crashed without reporting the playback stopped
+const STALE_PLAYBACK_TIMEOUT: Duration = Duration::from_secs(4 * 60 * 60);
+
+/// Playback on another device, as reported by a media server webhook.
+#[derive(Clone, Debug)]
+pub struct RemotePlayback {
+    pub player_name: String,
+    pub desktop_entry: String,
+    pub title: Title,
+    /// Name of the played item
+    pub media_title: String,
+    pub duration: Option<Duration>,
+    pub position: Duration,
+    pub playing: bool,
+    /// Whether the media server has reported the item as watched
+    pub watched: bool,
+}
+
+#[derive(Debug)]
+struct RemotePlaybackEntry {
+    playback: RemotePlayback,
+    updated: Instant,
+}
+
+/// Playbacks reported by the webhooks, by the ID of the player.
+#[derive(Debug, Default)]
+pub struct RemotePlaybacks {
+    playbacks: Mutex<HashMap<String, RemotePlaybackEntry>>,
+}
+
+impl RemotePlaybacks {
+    /// Updates the playback of given player. Once reported as watched, the
+    /// playback stays watched until another item is played, as some servers
+    /// report it before the playback stops.
+    pub fn update(&self, id: String, mut playback: RemotePlayback) {
+        let mut playbacks = self.playbacks.lock().unwrap();
+        if let Some(entry) = playbacks.get(&id)
+            && entry.playback.watched
+            && entry.playback.title == playback.title
+        {
+            playback.watched = true;
+        }
+
+        playbacks.insert(
+            id,
+            RemotePlaybackEntry {
+                playback,
+                updated: Instant::now(),
+            },
+        );
+    }
+
+    /// Forgets the playback of given player, unless it has been reported as
+    /// watched, so that it still gets scrobbled.
+    pub fn stop(&self, id: &str) {
+        let mut playbacks = self.playbacks.lock().unwrap();
+        match playbacks.get_mut(id) {
+            Some(entry) if entry.playback.watched => entry.playback.playing = false,
+            Some(_) => {
+                playbacks.remove(id);
+            }
+            None => {}
+        }
+    }
+
+    /// Returns the current playbacks, forgetting the ones that have expired.
+    pub(crate) fn players(&self) -> Vec<RemotePlayer> {
+        let mut playbacks = self.playbacks.lock().unwrap();
+        playbacks.retain(|_, entry| {
+            let timeout = if entry.playback.watched {
+                WATCHED_PLAYBACK_TIMEOUT
+            } else {
+                STALE_PLAYBACK_TIMEOUT
+            };
+            entry.updated.elapsed() < timeout
+        });
+
+        playbacks
+            .iter()
+            .map(|(id, entry)| RemotePlayer {
+                id: id.clone(),
+                playback: entry.playback.clone(),
+            })
+            .collect()
+    }
+}
+
+/// Snapshot of a [`RemotePlayback`].
+pub struct RemotePlayer {
+    id: String,
+    playback: RemotePlayback,
+}
+
+#[async_trait]
+impl MediaPlayer for RemotePlayer {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    async fn player_name(&self) -> PlayerControllerResult<String> {
+        Ok(self.playback.player_name.clone())
+    }
+
+    async fn desktop_entry(&self) -> PlayerControllerResult<String> {
+        Ok(self.playback.desktop_entry.clone())
+    }
+
+    /// Watched playbacks are reported as playing until they expire, so that
+    /// they get scrobbled even though they have already stopped.
+    async fn is_currently_playing(&self) -> PlayerControllerResult<bool> {
+        Ok(self.playback.playing || self.playback.watched)
+    }
+
+    async fn is_watched(&self) -> PlayerControllerResult<bool> {
+        Ok(self.playback.watched)
+    }
+
+    async fn title(&self) -> PlayerControllerResult<Option<Title>> {
+        Ok(Some(self.playback.title.clone()))
+    }
+
+    async fn title_played(&self) -> PlayerControllerResult<String> {
+        Ok(self.playback.media_title.clone())
+    }
+
+    async fn url_played(&self) -> PlayerControllerResult<String> {
+        Err(anyhow!("URL is not known for remote playbacks"))
+    }
+
+    async fn filename_played(&self) -> PlayerControllerResult<String> {
+        Err(anyhow!("filename is not known for remote playbacks"))
+    }
+
+    async fn duration(&self) -> PlayerControllerResult<Duration> {
+        self.playback
+            .duration
+            .ok_or(anyhow!("duration was not found"))
+    }
+
+    async fn position(&self) -> PlayerControllerResult<Duration> {
+        Ok(self.playback.position)
+    }
+}
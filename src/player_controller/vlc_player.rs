@@ -0,0 +1,339 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use log::debug;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::config::VlcConfig;
+use crate::player_controller::{MediaPlayer, PlayerControllerResult, filename_from_url};
+
+const PLAYER_NAME: &str = "VLC media player";
+const DESKTOP_ENTRY: &str = "vlc";
+
+/// VLC controlled through its Lua HTTP interface, for builds where the MPRIS
+/// support is missing or does not report the played file.
+pub struct VlcPlayer {
+    id: String,
+    client: reqwest::Client,
+    url: String,
+    password: String,
+    status: VlcStatus,
+}
+
+impl VlcPlayer {
+    /// Connects to VLC if its HTTP interface is configured. Returns `None`
+    /// if VLC is not running.
+    pub(super) async fn connect(client: &reqwest::Client, config: &VlcConfig) -> Option<Self> {
+        let password = config.password.clone()?;
+        let url = config.url.trim_end_matches('/').to_owned();
+
+        let status = match Self::request(client, &url, &password, "status.json").await {
+            Ok(status) => status,
+            Err(e) => {
+                debug!("Could not connect to VLC at {url}: {e}");
+                return None;
+            }
+        };
+
+        Some(Self {
+            id: format!("vlc-http:{url}"),
+            client: client.clone(),
+            url,
+            password,
+            status,
+        })
+    }
+
+    async fn request<T: DeserializeOwned>(
+        client: &reqwest::Client,
+        url: &str,
+        password: &str,
+        endpoint: &str,
+    ) -> PlayerControllerResult<T> {
+        Ok(client
+            .get(format!("{url}/requests/{endpoint}"))
+            .basic_auth("", Some(password))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+}
+
+#[async_trait]
+impl MediaPlayer for VlcPlayer {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    async fn player_name(&self) -> PlayerControllerResult<String> {
+        Ok(PLAYER_NAME.to_owned())
+    }
+
+    async fn desktop_entry(&self) -> PlayerControllerResult<String> {
+        Ok(DESKTOP_ENTRY.to_owned())
+    }
+
+    async fn is_currently_playing(&self) -> PlayerControllerResult<bool> {
+        Ok(self.status.state == "playing")
+    }
+
+    async fn title_played(&self) -> PlayerControllerResult<String> {
+        self.status
+            .meta("title")
+            .or_else(|| self.status.meta("filename"))
+            .map(str::to_owned)
+            .ok_or(anyhow!("Title was not found"))
+    }
+
+    /// Returns the URI of the current playlist item, as `status.json` does
+    /// not contain it.
+    async fn url_played(&self) -> PlayerControllerResult<String> {
+        let playlist: VlcPlaylistNode =
+            Self::request(&self.client, &self.url, &self.password, "playlist.json").await?;
+
+        playlist
+            .current_uri()
+            .map(str::to_owned)
+            .ok_or(anyhow!("URL was not found"))
+    }
+
+    /// Falls back to the title if the played item does not have a
+    /// meaningful filename, e.g. when streaming.
+    async fn filename_played(&self) -> PlayerControllerResult<String> {
+        match self.status.meta("filename").and_then(filename_from_url) {
+            Some(filename) => Ok(filename),
+            None => self.title_played().await,
+        }
+    }
+
+    async fn duration(&self) -> PlayerControllerResult<Duration> {
+        if self.status.length <= 0.0 {
+            return Err(anyhow!("duration was not found"));
+        }
+
+        Ok(Duration::try_from_secs_f64(self.status.length)?)
+    }
+
+    /// Returns the position computed from the relative one, as the `time`
+    /// field is rounded to seconds.
+    async fn position(&self) -> PlayerControllerResult<Duration> {
+        let position = self.status.position.clamp(0.0, 1.0) * self.status.length.max(0.0);
+
+        Ok(Duration::try_from_secs_f64(position)?)
+    }
+
+    async fn rate(&self) -> PlayerControllerResult<f64> {
+        Ok(self.status.rate)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct VlcStatus {
+    /// "playing", "paused" or "stopped"
+    state: String,
+    /// In seconds
+    #[serde(default)]
+    length: f64,
+    /// Between 0 and 1
+    #[serde(default)]
+    position: f64,
+    #[serde(default = "default_rate")]
+    rate: f64,
+    information: Option<VlcInformation>,
+}
+
+impl VlcStatus {
+    fn meta(&self, key: &str) -> Option<&str> {
+        self.information
+            .as_ref()?
+            .category
+            .meta
+            .get(key)?
+            .as_str()
+            .filter(|x| !x.is_empty())
+    }
+}
+
+fn default_rate() -> f64 {
+    1.0
+}
+
+#[derive(Debug, Deserialize)]
+struct VlcInformation {
+    category: VlcCategories,
+}
+
+#[derive(Debug, Deserialize)]
+struct VlcCategories {
+    #[serde(default)]
+    meta: HashMap<String, Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct VlcPlaylistNode {
+    uri: Option<String>,
+    /// "current" for the item being played
+    current: Option<String>,
+    #[serde(default)]
+    children: Vec<VlcPlaylistNode>,
+}
+
+impl VlcPlaylistNode {
+    fn current_uri(&self) -> Option<&str> {
+        if self.current.is_some() {
+            return self.uri.as_deref();
+        }
+
+        self.children.iter().find_map(Self::current_uri)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn make_player(status: Value) -> VlcPlayer {
+        VlcPlayer {
+            id: "vlc-http:http://localhost:8080".to_owned(),
+            client: reqwest::Client::new(),
+            url: "http://localhost:8080".to_owned(),
+            password: "password".to_owned(),
+            status: serde_json::from_value(status).unwrap(),
+        }
+    }
+
+    fn playing_status() -> Value {
+        json!({
+            "fullscreen": false,
+            "state": "playing",
+            "time": 300,
+            "length": 1440,
+            "position": 0.25,
+            "rate": 1.5,
+            "volume": 256,
+            "information": {
+                "chapter": 0,
+                "category": {
+                    "meta": {
+                        "filename": "[SubsPlease] Sousou no Frieren - 05 (1080p) [ABCD1234].mkv",
+                        "title": "Phantoms of the Dead",
+                    },
+                    "Stream 0": {"Type": "Video", "Codec": "H264 - MPEG-4 AVC (part 10) (avc1)"},
+                },
+            },
+        })
+    }
+
+    #[tokio::test]
+    async fn reads_playing_status() {
+        let player = make_player(playing_status());
+
+        assert!(player.is_currently_playing().await.unwrap());
+        assert_eq!(player.title_played().await.unwrap(), "Phantoms of the Dead");
+        assert_eq!(
+            player.filename_played().await.unwrap(),
+            "[SubsPlease] Sousou no Frieren - 05 (1080p) [ABCD1234].mkv"
+        );
+        assert_eq!(
+            player.duration().await.unwrap(),
+            Duration::from_secs(24 * 60)
+        );
+        assert_eq!(
+            player.position().await.unwrap(),
+            Duration::from_secs(6 * 60)
+        );
+        assert_eq!(player.rate().await.unwrap(), 1.5);
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_filename_without_title() {
+        let mut status = playing_status();
+        status["information"]["category"]["meta"]["title"] = json!("");
+        let player = make_player(status);
+
+        assert_eq!(
+            player.title_played().await.unwrap(),
+            "[SubsPlease] Sousou no Frieren - 05 (1080p) [ABCD1234].mkv"
+        );
+    }
+
+    #[tokio::test]
+    async fn reads_paused_and_stopped_status() {
+        let mut status = playing_status();
+        status["state"] = json!("paused");
+        let player = make_player(status);
+        assert!(!player.is_currently_playing().await.unwrap());
+        assert_eq!(
+            player.position().await.unwrap(),
+            Duration::from_secs(6 * 60)
+        );
+
+        let player = make_player(json!({
+            "state": "stopped",
+            "time": 0,
+            "length": 0,
+            "position": 0,
+        }));
+        assert!(!player.is_currently_playing().await.unwrap());
+        assert!(player.title_played().await.is_err());
+        assert!(player.duration().await.is_err());
+        assert_eq!(player.position().await.unwrap(), Duration::ZERO);
+        assert_eq!(player.rate().await.unwrap(), 1.0);
+    }
+
+    #[test]
+    fn finds_current_playlist_item() {
+        let playlist: VlcPlaylistNode = serde_json::from_value(json!({
+            "ro": "rw",
+            "type": "node",
+            "name": "",
+            "id": "1",
+            "children": [
+                {
+                    "ro": "ro",
+                    "type": "node",
+                    "name": "Playlist",
+                    "id": "2",
+                    "children": [
+                        {
+                            "ro": "rw",
+                            "type": "leaf",
+                            "name": "Sousou no Frieren - 04.mkv",
+                            "id": "4",
+                            "duration": 1440,
+                            "uri": "file:///media/anime/Sousou%20no%20Frieren%20-%2004.mkv",
+                        },
+                        {
+                            "ro": "rw",
+                            "type": "leaf",
+                            "name": "Sousou no Frieren - 05.mkv",
+                            "id": "5",
+                            "duration": 1440,
+                            "uri": "file:///media/anime/Sousou%20no%20Frieren%20-%2005.mkv",
+                            "current": "current",
+                        },
+                    ],
+                },
+                {"ro": "ro", "type": "node", "name": "Media Library", "id": "3", "children": []},
+            ],
+        }))
+        .unwrap();
+
+        assert_eq!(
+            playlist.current_uri(),
+            Some("file:///media/anime/Sousou%20no%20Frieren%20-%2005.mkv")
+        );
+
+        let playlist: VlcPlaylistNode =
+            serde_json::from_value(json!({"type": "node", "id": "1", "children": []})).unwrap();
+        assert_eq!(playlist.current_uri(), None);
+    }
+}
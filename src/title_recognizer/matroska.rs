@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anitomy::ElementCategory;
+use log::debug;
+use url::Url;
+
+use crate::matroska::{MatroskaTags, TARGET_COLLECTION, TARGET_EPISODE, TARGET_SEASON};
+use crate::title_recognizer::normalization::strip_release_tags;
+use crate::title_recognizer::{AnitomyRecognizer, PlayedMedia, Recognition, Recognizer, Title};
+
+const MATROSKA_EXTENSIONS: &[&str] = &["mkv", "mk3d", "webm"];
+
+/// Recognizes titles from the tags and the segment title embedded in local
+/// Matroska files.
+#[derive(Default)]
+pub struct MatroskaRecognizer {
+    cache: HashMap<PathBuf, Option<Recognition>>,
+}
+
+impl MatroskaRecognizer {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    fn local_path(media: &PlayedMedia) -> Option<PathBuf> {
+        let path = Url::parse(media.url.as_deref()?)
+            .ok()?
+            .to_file_path()
+            .ok()?;
+        let extension = path.extension()?.to_str()?;
+
+        MATROSKA_EXTENSIONS
+            .iter()
+            .any(|x| x.eq_ignore_ascii_case(extension))
+            .then_some(path)
+    }
+
+    fn read_title(path: &Path, filename: Option<&str>) -> Option<Recognition> {
+        let tags = match MatroskaTags::read(path) {
+            Ok(tags) => tags,
+            Err(e) => {
+                debug!("Could not read Matroska tags from {}: {e}", path.display());
+                return None;
+            }
+        };
+        debug!("Found Matroska tags: {tags:?}");
+
+        Self::title_from_tags(&tags).or_else(|| {
+            [
+                tags.get(TARGET_EPISODE, "TITLE"),
+                tags.segment_title.as_deref(),
+            ]
+            .into_iter()
+            .flatten()
+            .find_map(|text| Self::title_from_text(text, filename))
+        })
+    }
+
+    fn title_from_tags(tags: &MatroskaTags) -> Option<Recognition> {
+        let series = tags.get(TARGET_COLLECTION, "TITLE")?.trim();
+        let episode_number: i32 = tags.get(TARGET_EPISODE, "PART_NUMBER")?.parse().ok()?;
+        let season_tag = tags.get(TARGET_SEASON, "PART_NUMBER");
+        let season_number: i32 = match season_tag {
+            Some(season_number) => season_number.parse().ok()?,
+            None => 1,
+        };
+        if series.is_empty() || episode_number < 1 || season_number < 1 {
+            return None;
+        }
+
+        Some(Recognition::new(
+            Title::new(series.to_owned(), season_number, episode_number),
+            season_tag.is_some(),
+            true,
+        ))
+    }
+
+    /// Parses a free-form title (such as "[Group] Show - 05"). If it does not
+    /// contain the episode number, it is taken from the filename instead.
+    fn title_from_text(text: &str, filename: Option<&str>) -> Option<Recognition> {
+        let elements = AnitomyRecognizer::parse(text)?;
+        if elements.get(ElementCategory::EpisodeNumber).is_some() {
+            return AnitomyRecognizer::elements_to_title(&elements);
+        }
+
+        let filename_elements = AnitomyRecognizer::parse(filename?)?;
+        let episode_number: i32 = filename_elements
+            .get(ElementCategory::EpisodeNumber)?
+            .parse()
+            .ok()?;
+        let season = elements
+            .get(ElementCategory::AnimeSeason)
+            .or(filename_elements.get(ElementCategory::AnimeSeason));
+        let season_number: i32 = season.unwrap_or("1").parse().ok()?;
+        let title = strip_release_tags(elements.get(ElementCategory::AnimeTitle)?);
+        if episode_number < 1 {
+            return None;
+        }
+
+        Some(Recognition::new(
+            Title::new(title, season_number, episode_number),
+            season.is_some(),
+            true,
+        ))
+    }
+}
+
+impl Recognizer for MatroskaRecognizer {
+    fn name(&self) -> &'static str {
+        "matroska"
+    }
+
+    fn reliability(&self) -> f32 {
+        0.8
+    }
+
+    fn recognize(&mut self, media: &PlayedMedia) -> Option<Recognition> {
+        let path = Self::local_path(media)?;
+
+        self.cache
+            .entry(path)
+            .or_insert_with_key(|path| Self::read_title(path, media.filename.as_deref()))
+            .clone()
+    }
+}
@@ -0,0 +1,236 @@
+use std::time::Duration;
+
+use log::debug;
+
+use crate::player_controller::Metadata;
+use crate::title_recognizer::{PlayedMedia, Recognition, Recognizer, Title};
+
+/// Field names (without the `xesam:`/`kodi:`/... namespace) that may hold the
+/// series name.
+const SERIES_FIELDS: &[&str] = &["series", "seriesName", "showTitle", "album"];
+/// Field names that may hold the season number.
+const SEASON_FIELDS: &[&str] = &["season", "seasonNumber", "parentIndexNumber"];
+/// Field names that may hold the episode number.
+const EPISODE_FIELDS: &[&str] = &["episode", "episodeNumber", "indexNumber", "trackNumber"];
+
+/// Without explicit season metadata, an album with a track number could just
+/// as well be a music track, so only videos at least this long are
+/// considered episodes.
+const MIN_EPISODE_LENGTH: Duration = Duration::from_secs(10 * 60);
+
+/// Recognizes titles from structured MPRIS metadata, as published by media
+/// center players such as Jellyfin Media Player or Kodi.
+#[derive(Default)]
+pub struct MetadataRecognizer;
+
+impl MetadataRecognizer {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Returns the first key matching one of the field names (in order of
+    /// preference) whose value can be read with `read`, along with the value.
+    /// Keys with empty or invalid values are skipped.
+    fn find_field<'a, T>(
+        metadata: &'a Metadata,
+        fields: &[&str],
+        read: impl Fn(&'a Metadata, &str) -> Option<T>,
+    ) -> Option<(&'a str, T)> {
+        fields.iter().find_map(|field| {
+            metadata
+                .keys()
+                .filter(|key| Self::field_name(key).eq_ignore_ascii_case(field))
+                .find_map(|key| read(metadata, key).map(|value| (key, value)))
+        })
+    }
+
+    fn read_str<'a>(metadata: &'a Metadata, key: &str) -> Option<&'a str> {
+        Some(metadata.get_str(key)?.trim()).filter(|x| !x.is_empty())
+    }
+
+    fn read_number(metadata: &Metadata, key: &str) -> Option<i32> {
+        i32::try_from(metadata.get_integer(key)?).ok()
+    }
+
+    fn field_name(key: &str) -> &str {
+        key.rsplit_once(':').map_or(key, |(_namespace, name)| name)
+    }
+
+    fn is_long_enough(metadata: &Metadata) -> bool {
+        metadata
+            .get_integer("mpris:length")
+            .is_some_and(|length| Duration::from_micros(length as u64) >= MIN_EPISODE_LENGTH)
+    }
+}
+
+impl Recognizer for MetadataRecognizer {
+    fn name(&self) -> &'static str {
+        "metadata"
+    }
+
+    fn reliability(&self) -> f32 {
+        0.9
+    }
+
+    fn recognize(&mut self, media: &PlayedMedia) -> Option<Recognition> {
+        let metadata = &media.metadata;
+
+        let (series_key, series) = Self::find_field(metadata, SERIES_FIELDS, Self::read_str)?;
+        let (episode_key, episode_number) =
+            Self::find_field(metadata, EPISODE_FIELDS, Self::read_number)?;
+        let season = Self::find_field(metadata, SEASON_FIELDS, Self::read_number);
+        if season.is_none() && !Self::is_long_enough(metadata) {
+            return None;
+        }
+
+        let season_key = season.map(|(key, _)| key);
+        let season_number = season.map_or(1, |(_, number)| number);
+        if episode_number < 1 || season_number < 1 {
+            return None;
+        }
+
+        debug!("Found structured metadata: {series_key}, {season_key:?}, {episode_key}");
+        Some(Recognition::new(
+            Title::new(series.to_owned(), season_number, episode_number),
+            season_key.is_some(),
+            true,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use dbus::arg::{RefArg, Variant};
+
+    use super::*;
+
+    fn media(fields: Vec<(&str, Box<dyn RefArg>)>) -> PlayedMedia {
+        let metadata: HashMap<_, _> = fields
+            .into_iter()
+            .map(|(key, value)| (key.to_owned(), Variant(value)))
+            .collect();
+
+        PlayedMedia {
+            title: None,
+            filename: None,
+            url: None,
+            duration: None,
+            metadata: Metadata::from_dbus(&metadata),
+        }
+    }
+
+    fn recognize(fields: Vec<(&str, Box<dyn RefArg>)>) -> Option<(Title, bool)> {
+        MetadataRecognizer::new()
+            .recognize(&media(fields))
+            .map(|x| (x.title, x.season_found))
+    }
+
+    fn title(title: &str, season_number: i32, episode_number: i32) -> Title {
+        Title::new(title.to_owned(), season_number, episode_number)
+    }
+
+    fn length(minutes: i64) -> Box<dyn RefArg> {
+        Box::new(minutes * 60 * 1_000_000)
+    }
+
+    #[test]
+    fn reads_namespaced_fields() {
+        let recognition = recognize(vec![
+            ("xesam:title", Box::new("The Journey's End".to_owned())),
+            ("xesam:series", Box::new("Sousou no Frieren".to_owned())),
+            ("xesam:season", Box::new(2)),
+            ("xesam:episode", Box::new(5)),
+        ]);
+        assert_eq!(recognition, Some((title("Sousou no Frieren", 2, 5), true)));
+
+        let recognition = recognize(vec![
+            (
+                "jellyfin:seriesName",
+                Box::new(" Sousou no Frieren ".to_owned()),
+            ),
+            ("jellyfin:parentIndexNumber", Box::new(1)),
+            ("jellyfin:IndexNumber", Box::new(3)),
+        ]);
+        assert_eq!(recognition, Some((title("Sousou no Frieren", 1, 3), true)));
+    }
+
+    #[test]
+    fn parses_numbers_given_as_strings_and_floats() {
+        let recognition = recognize(vec![
+            ("kodi:showTitle", Box::new("Sousou no Frieren".to_owned())),
+            ("kodi:seasonNumber", Box::new("2".to_owned())),
+            ("kodi:episodeNumber", Box::new(7.0)),
+        ]);
+        assert_eq!(recognition, Some((title("Sousou no Frieren", 2, 7), true)));
+    }
+
+    #[test]
+    fn skips_fields_with_invalid_values() {
+        let recognition = recognize(vec![
+            ("xesam:series", Box::new(" ".to_owned())),
+            ("xesam:album", Box::new("Sousou no Frieren".to_owned())),
+            ("xesam:season", Box::new("unknown".to_owned())),
+            ("xesam:seasonNumber", Box::new(2)),
+            ("xesam:episode", Box::new(String::new())),
+            ("xesam:trackNumber", Box::new(4)),
+        ]);
+        assert_eq!(recognition, Some((title("Sousou no Frieren", 2, 4), true)));
+    }
+
+    #[test]
+    fn reads_album_and_track_number_of_long_media() {
+        let recognition = recognize(vec![
+            ("xesam:album", Box::new("Sousou no Frieren".to_owned())),
+            ("xesam:trackNumber", Box::new(6)),
+            ("mpris:length", length(24)),
+        ]);
+        assert_eq!(recognition, Some((title("Sousou no Frieren", 1, 6), false)));
+    }
+
+    #[test]
+    fn ignores_music_tracks() {
+        let track = |length_minutes| {
+            recognize(vec![
+                ("xesam:album", Box::new("Sousou no Frieren OST".to_owned())),
+                ("xesam:trackNumber", Box::new(6)),
+                ("mpris:length", length(length_minutes)),
+            ])
+        };
+        assert_eq!(track(3), None);
+        assert_eq!(
+            track(10).map(|(title, _)| title),
+            Some(title("Sousou no Frieren OST", 1, 6))
+        );
+
+        let recognition = recognize(vec![
+            ("xesam:album", Box::new("Sousou no Frieren OST".to_owned())),
+            ("xesam:trackNumber", Box::new(6)),
+        ]);
+        assert_eq!(recognition, None);
+    }
+
+    #[test]
+    fn ignores_incomplete_metadata() {
+        let recognition = recognize(vec![
+            ("xesam:title", Box::new("Sousou no Frieren - 05".to_owned())),
+            ("mpris:length", length(24)),
+        ]);
+        assert_eq!(recognition, None);
+
+        let recognition = recognize(vec![
+            ("xesam:series", Box::new("Sousou no Frieren".to_owned())),
+            ("xesam:season", Box::new(1)),
+        ]);
+        assert_eq!(recognition, None);
+
+        // Specials
+        let recognition = recognize(vec![
+            ("xesam:series", Box::new("Sousou no Frieren".to_owned())),
+            ("xesam:season", Box::new(0)),
+            ("xesam:episode", Box::new(1)),
+        ]);
+        assert_eq!(recognition, None);
+    }
+}
@@ -0,0 +1,477 @@
+use std::cell::RefCell;
+use std::str::FromStr;
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use anitomy::{Anitomy, ElementCategory, Elements};
+use log::{debug, warn};
+use regex::Regex;
+
+use crate::config::{RecognitionConfig, RecognitionStrategy};
+use crate::player_controller::Metadata;
+use crate::title_recognizer::matroska::MatroskaRecognizer;
+use crate::title_recognizer::metadata::MetadataRecognizer;
+use crate::title_recognizer::normalization::{normalize_title, strip_release_tags};
+use crate::title_recognizer::season::split_season_suffix;
+use crate::title_recognizer::streaming_site::StreamingSiteRecognizer;
+
+mod matroska;
+mod metadata;
+mod normalization;
+mod season;
+mod streaming_site;
+
+/// "Kimi no Na wa. (Movie)", "Gekijouban Made in Abyss", "劇場版 呪術廻戦 0"
+const MOVIE_KEYWORD_PATTERN: &str = r"(?i)\b(?:movie|gekijou?ban)\b|劇場版";
+/// Files without an episode number that are at least this long are assumed
+/// to be movies.
+const MOVIE_MIN_DURATION: Duration = Duration::from_secs(60 * 60);
+
+/// How much the confidence is lowered if the episode number of a title that
+/// is not a movie has not been found
+const MISSING_EPISODE_FACTOR: f32 = 0.6;
+/// How much the confidence is lowered if the season number has not been
+/// found. Most titles are first seasons, so it is just a hint.
+const MISSING_SEASON_FACTOR: f32 = 0.9;
+/// How much the confidence is lowered if the title consists only of
+/// punctuation
+const EMPTY_TITLE_FACTOR: f32 = 0.3;
+
+static MOVIE_KEYWORD_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(MOVIE_KEYWORD_PATTERN).unwrap());
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Title {
+    pub title: String,
+    pub season_number: i32,
+    pub episode_number: i32,
+    /// Part (or cour) of the season, for seasons split into several entries
+    /// (e.g. "Season 3 Part 2").
+    pub part_number: i32,
+    /// Whether the title refers to the final season, whose number is not
+    /// known.
+    pub final_season: bool,
+    /// Whether the title looks like a movie (or another single-episode
+    /// entry) rather than an episode of a series.
+    pub movie: bool,
+}
+
+impl Title {
+    pub fn new(title: String, season_number: i32, episode_number: i32) -> Self {
+        Self {
+            title,
+            season_number,
+            episode_number,
+            part_number: 1,
+            final_season: false,
+            movie: false,
+        }
+    }
+
+    /// Returns whether the title refers to the first entry of the series.
+    pub fn is_first_season(&self) -> bool {
+        self.season_number == 1 && self.part_number == 1 && !self.final_season
+    }
+
+    pub fn normalized_key(&self) -> String {
+        normalize_key(&self.title)
+    }
+
+    /// Returns the title to search for in anime databases, without the
+    /// "Movie" keyword, which is not a part of the title of most movies.
+    pub fn search_query(&self) -> String {
+        let query = MOVIE_KEYWORD_REGEX.replace_all(&self.title, " ");
+        let query = query.split_whitespace().collect::<Vec<_>>().join(" ");
+
+        if query.is_empty() {
+            self.title.clone()
+        } else {
+            query
+        }
+    }
+}
+
+/// Returns the title in lowercase and with anything but letters and digits
+/// removed, so that it can be used as a lookup key.
+pub fn normalize_key(title: &str) -> String {
+    title
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Information about the media being played, as reported by the player.
+#[derive(Clone, Debug, Default)]
+pub struct PlayedMedia {
+    pub title: Option<String>,
+    pub filename: Option<String>,
+    pub url: Option<String>,
+    pub duration: Option<Duration>,
+    pub metadata: Metadata,
+}
+
+/// A title found by a recognizer, along with whether its numbers have
+/// actually been found or just assumed to be 1.
+#[derive(Clone, Debug)]
+pub(crate) struct Recognition {
+    pub title: Title,
+    pub season_found: bool,
+    pub episode_found: bool,
+}
+
+impl Recognition {
+    pub fn new(title: Title, season_found: bool, episode_found: bool) -> Self {
+        Self {
+            title,
+            season_found,
+            episode_found,
+        }
+    }
+}
+
+pub(crate) trait Recognizer: Send + Sync {
+    /// Short name of the recognizer, used in the config and when explaining
+    /// the recognition.
+    fn name(&self) -> &'static str;
+
+    /// How reliable the source of the titles returned by this recognizer is,
+    /// between 0 and 1. The confidence of each title is based on it.
+    fn reliability(&self) -> f32;
+
+    fn recognize(&mut self, media: &PlayedMedia) -> Option<Recognition>;
+}
+
+/// The result of running a single recognizer.
+#[derive(Clone, Debug)]
+pub struct RecognitionStep {
+    pub recognizer: &'static str,
+    pub confidence: f32,
+    pub title: Option<Title>,
+}
+
+#[derive(Default)]
+pub struct TitleRecognizer {
+    recognizers: Vec<Box<dyn Recognizer>>,
+    strategy: RecognitionStrategy,
+}
+
+impl TitleRecognizer {
+    pub fn new(config: &RecognitionConfig) -> Self {
+        let recognizers = config
+            .recognizers
+            .iter()
+            .filter_map(|name| {
+                let recognizer = Self::make_recognizer(name);
+                if recognizer.is_none() {
+                    warn!("Unknown recognizer in the config: {name}");
+                }
+                recognizer
+            })
+            .collect();
+
+        Self {
+            recognizers,
+            strategy: config.strategy,
+        }
+    }
+
+    fn make_recognizer(name: &str) -> Option<Box<dyn Recognizer>> {
+        let recognizer: Box<dyn Recognizer> = match name {
+            "metadata" => Box::new(MetadataRecognizer::new()),
+            "streaming-site" => Box::new(StreamingSiteRecognizer::new()),
+            "ani-cli" => Box::new(AniCliRecognizer::new()),
+            "matroska" => Box::new(MatroskaRecognizer::new()),
+            "anitomy" => Box::new(AnitomyRecognizer::new()),
+            _ => return None,
+        };
+
+        Some(recognizer)
+    }
+
+    pub fn recognize(&mut self, media: &PlayedMedia) -> Option<Title> {
+        let steps = match self.strategy {
+            RecognitionStrategy::FirstMatch => {
+                let mut steps = Vec::new();
+                for recognizer in &mut self.recognizers {
+                    let step = Self::run(recognizer.as_mut(), media);
+                    let matched = step.title.is_some();
+                    steps.push(step);
+                    if matched {
+                        break;
+                    }
+                }
+                steps
+            }
+            RecognitionStrategy::BestConfidence => self.recognize_all(media),
+        };
+
+        for step in &steps {
+            debug!(
+                "Recognizer {} (confidence {}): {:?}",
+                step.recognizer, step.confidence, step.title
+            );
+        }
+        let step = self.choose(&steps)?;
+        debug!(
+            "Picked the title recognized by {} using the {:?} strategy",
+            step.recognizer, self.strategy
+        );
+
+        step.title.clone()
+    }
+
+    /// Runs every recognizer (not only until the first match) and returns
+    /// their results. Useful for debugging.
+    pub fn recognize_all(&mut self, media: &PlayedMedia) -> Vec<RecognitionStep> {
+        self.recognizers
+            .iter_mut()
+            .map(|x| Self::run(x.as_mut(), media))
+            .collect()
+    }
+
+    /// Picks the step whose title should be used, according to the
+    /// configured strategy.
+    pub fn choose<'a>(&self, steps: &'a [RecognitionStep]) -> Option<&'a RecognitionStep> {
+        let mut matched = steps.iter().filter(|x| x.title.is_some());
+
+        match self.strategy {
+            RecognitionStrategy::FirstMatch => matched.next(),
+            RecognitionStrategy::BestConfidence => matched.reduce(|best, x| {
+                if x.confidence > best.confidence {
+                    x
+                } else {
+                    best
+                }
+            }),
+        }
+    }
+
+    fn run(recognizer: &mut dyn Recognizer, media: &PlayedMedia) -> RecognitionStep {
+        let recognition = recognizer.recognize(media);
+
+        RecognitionStep {
+            recognizer: recognizer.name(),
+            confidence: recognition
+                .as_ref()
+                .map_or(0.0, |x| Self::confidence(recognizer.reliability(), x)),
+            title: recognition.map(|x| Self::post_process(x.title)),
+        }
+    }
+
+    /// Returns how likely the recognized title is to be right, between 0 and
+    /// 1: the reliability of the recognizer, lowered if the episode or season
+    /// number was not found, or if nothing is left of the title after
+    /// normalization (e.g. when it is just a number in brackets).
+    fn confidence(reliability: f32, recognition: &Recognition) -> f32 {
+        let mut confidence = reliability;
+        if !recognition.episode_found && !recognition.title.movie {
+            confidence *= MISSING_EPISODE_FACTOR;
+        }
+        if !recognition.season_found {
+            confidence *= MISSING_SEASON_FACTOR;
+        }
+        if normalize_title(&recognition.title.title).is_empty() {
+            confidence *= EMPTY_TITLE_FACTOR;
+        }
+
+        confidence
+    }
+
+    /// Cleans up the title returned by a recognizer, so that it can be
+    /// searched for.
+    pub fn post_process(mut title: Title) -> Title {
+        if MOVIE_KEYWORD_REGEX.is_match(&title.title) {
+            title.movie = true;
+        }
+        let normalized_title = normalize_title(&title.title);
+        if !normalized_title.is_empty() {
+            title.title = normalized_title;
+        }
+        split_season_suffix(&mut title);
+
+        title
+    }
+
+    /// Returns the elements found by anitomy in given text.
+    pub fn anitomy_elements(text: &str) -> Option<Elements> {
+        AnitomyRecognizer::parse(text)
+    }
+}
+
+#[derive(Default)]
+struct AnitomyRecognizer;
+
+thread_local! {
+    static ANITOMY: RefCell<Anitomy> = RefCell::new(Anitomy::new());
+}
+
+impl AnitomyRecognizer {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    fn parse(text: &str) -> Option<Elements> {
+        ANITOMY.with(|anitomy| anitomy.borrow_mut().parse(text).ok())
+    }
+
+    fn elements_to_title(elements: &Elements) -> Option<Recognition> {
+        debug!("Found path elements: {elements:?}");
+        let title = strip_release_tags(elements.get(ElementCategory::AnimeTitle)?);
+
+        let episode_number: i32 = elements
+            .get(ElementCategory::EpisodeNumber)
+            .unwrap_or("1")
+            .parse()
+            .ok()?;
+        if episode_number < 1 {
+            return None;
+        }
+
+        let season_number: i32 = elements
+            .get(ElementCategory::AnimeSeason)
+            .unwrap_or("1")
+            .parse()
+            .ok()?;
+
+        let mut title = Title::new(title, season_number, episode_number);
+        title.movie = elements
+            .get_all(ElementCategory::AnimeType)
+            .iter()
+            .any(|x| MOVIE_KEYWORD_REGEX.is_match(x));
+
+        Some(Recognition::new(
+            title,
+            elements.get(ElementCategory::AnimeSeason).is_some(),
+            elements.get(ElementCategory::EpisodeNumber).is_some(),
+        ))
+    }
+}
+
+impl Recognizer for AnitomyRecognizer {
+    fn name(&self) -> &'static str {
+        "anitomy"
+    }
+
+    fn reliability(&self) -> f32 {
+        0.5
+    }
+
+    fn recognize(&mut self, media: &PlayedMedia) -> Option<Recognition> {
+        let elements = Self::parse(media.filename.as_deref()?)?;
+        let mut recognition = Self::elements_to_title(&elements)?;
+
+        if !recognition.episode_found && media.duration.is_some_and(|x| x >= MOVIE_MIN_DURATION) {
+            debug!("No episode number and long duration, assuming a movie");
+            recognition.title.movie = true;
+        }
+
+        Some(recognition)
+    }
+}
+
+#[derive(Default)]
+struct AniCliRecognizer;
+
+impl AniCliRecognizer {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl Recognizer for AniCliRecognizer {
+    fn name(&self) -> &'static str {
+        "ani-cli"
+    }
+
+    fn reliability(&self) -> f32 {
+        0.6
+    }
+
+    fn recognize(&mut self, media: &PlayedMedia) -> Option<Recognition> {
+        if let Some(title) = &media.title {
+            let separator = if title.starts_with("ani-cli: ") {
+                " ep "
+            } else if title.contains(" Episode ") {
+                " Episode "
+            } else {
+                return None;
+            };
+            let (title, episode_number_str) = title.split_once(separator)?;
+            let episode_number = i32::from_str(episode_number_str).ok()?;
+
+            return Some(Recognition::new(
+                Title::new(title.to_owned(), 1, episode_number),
+                false,
+                true,
+            ));
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn anitomy_confidence(filename: &str, duration: Option<Duration>) -> f32 {
+        let media = PlayedMedia {
+            filename: Some(filename.to_owned()),
+            duration,
+            ..Default::default()
+        };
+
+        TitleRecognizer::run(&mut AnitomyRecognizer::new(), &media).confidence
+    }
+
+    #[test]
+    fn confidence_depends_on_found_numbers() {
+        let full = anitomy_confidence("[Group] Sousou no Frieren S02E05 [1080p].mkv", None);
+        let no_season = anitomy_confidence("[Group] Sousou no Frieren - 05 [1080p].mkv", None);
+        let no_episode = anitomy_confidence("[Group] Sousou no Frieren [1080p].mkv", None);
+
+        assert!(full > no_season, "{full} > {no_season}");
+        assert!(no_season > no_episode, "{no_season} > {no_episode}");
+    }
+
+    #[test]
+    fn movies_do_not_need_episode_number() {
+        let short = anitomy_confidence("Kimi no Na wa.mkv", Some(Duration::from_secs(60)));
+        let movie = anitomy_confidence("Kimi no Na wa.mkv", Some(MOVIE_MIN_DURATION));
+
+        assert!(movie > short, "{movie} > {short}");
+    }
+
+    #[test]
+    fn confidence_is_lowered_for_empty_titles() {
+        let title = Title::new("!!".to_owned(), 1, 1);
+        let confidence = TitleRecognizer::confidence(1.0, &Recognition::new(title, true, true));
+
+        assert!(confidence < 0.5, "{confidence}");
+    }
+
+    #[test]
+    fn search_query_omits_movie_keyword() {
+        let cases = [
+            ("Shingeki no Kyojin Movie 1", "Shingeki no Kyojin 1"),
+            ("Gekijouban Violet Evergarden", "Violet Evergarden"),
+            ("劇場版 呪術廻戦 0", "呪術廻戦 0"),
+            ("Movie", "Movie"),
+            ("Sousou no Frieren", "Sousou no Frieren"),
+        ];
+
+        for (input, expected) in cases {
+            let title = Title::new(input.to_owned(), 1, 1);
+            assert_eq!(title.search_query(), expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn unrecognized_media_has_no_confidence() {
+        let step = TitleRecognizer::run(&mut AniCliRecognizer::new(), &PlayedMedia::default());
+
+        assert!(step.title.is_none());
+        assert_eq!(step.confidence, 0.0);
+    }
+}
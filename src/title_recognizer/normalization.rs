@@ -0,0 +1,137 @@
+use std::sync::LazyLock;
+
+use regex::Regex;
+use unicode_normalization::UnicodeNormalization;
+
+/// Release tags that anitomy sometimes leaves in the title
+const RELEASE_TAGS: &str = r"bd|bdrip|blu-?ray|web|web-?dl|webrip|dvd|dvdrip|hevc|avc|x26[45]|h\.?26[45]|aac|flac|opus|10-?bit|8-?bit|hi10p?|\d{3,4}p|4k|dual[ -]audio|multi[ -]subs?|raw|batch";
+/// Episode markers, such as "Show - 01 1080p" or "Show EP01 WEB"; anything
+/// after them is not a part of the title.
+const EPISODE_MARKER_PATTERN: &str = r"(?i)(?:\s-\s\d{1,4}|\bEP?\d{1,4})\b";
+
+/// Brackets containing only release tags or a CRC32 checksum, such as
+/// "[1080p]", "(BD 1080p HEVC)" or "[1A2B3C4D]"
+static BRACKETED_TAGS_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!(
+        r"(?i)[\[(](?:[\s,+_-]*(?:{RELEASE_TAGS}|[0-9a-f]{{8}})\b)+[\s,+_-]*[\])]"
+    ))
+    .unwrap()
+});
+static RELEASE_TAG_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(&format!(r"(?i)\b(?:{RELEASE_TAGS})\b")).unwrap());
+static EPISODE_MARKER_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(EPISODE_MARKER_PATTERN).unwrap());
+
+/// Cleans up a recognized title before it is searched for: applies Unicode
+/// NFKC normalization (so that e.g. full-width characters become ASCII),
+/// removes punctuation other than apostrophes, and collapses whitespace.
+pub fn normalize_title(title: &str) -> String {
+    let title: String = title
+        .nfkc()
+        .map(|c| match c {
+            '’' => '\'',
+            c if c.is_alphanumeric() || c == '\'' => c,
+            _ => ' ',
+        })
+        .collect();
+
+    title.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Removes the release tags that anitomy sometimes leaves in titles parsed
+/// from filenames. Only the tags in brackets or after an episode marker are
+/// removed, as the same words can be a part of the title (e.g. "Opus.COLORs"
+/// or "Raw Hero"); titles that come from metadata should not be passed here.
+pub fn strip_release_tags(title: &str) -> String {
+    let title = BRACKETED_TAGS_REGEX.replace_all(title, " ");
+
+    let result = match EPISODE_MARKER_REGEX.find(&title) {
+        Some(marker) => {
+            let (head, tail) = title.split_at(marker.end());
+            format!("{head}{}", RELEASE_TAG_REGEX.replace_all(tail, " "))
+        }
+        None => title.into_owned(),
+    };
+
+    result.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_romaji_titles() {
+        let cases = [
+            ("Sousou no Frieren", "Sousou no Frieren"),
+            ("Kaguya-sama wa Kokurasetai", "Kaguya sama wa Kokurasetai"),
+            (
+                "Re:Zero kara Hajimeru Isekai Seikatsu",
+                "Re Zero kara Hajimeru Isekai Seikatsu",
+            ),
+            ("  Bocchi   the  Rock!  ", "Bocchi the Rock"),
+            ("Ｓｏｕｓｏｕ ｎｏ Ｆｒｉｅｒｅｎ", "Sousou no Frieren"),
+            ("Steins;Gate", "Steins Gate"),
+            ("JoJo's Bizarre Adventure", "JoJo's Bizarre Adventure"),
+            ("JoJo’s Bizarre Adventure", "JoJo's Bizarre Adventure"),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(normalize_title(input), expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn normalizes_japanese_titles() {
+        let cases = [
+            ("葬送のフリーレン", "葬送のフリーレン"),
+            ("【推しの子】", "推しの子"),
+            (
+                "かぐや様は告らせたい～天才たちの恋愛頭脳戦～",
+                "かぐや様は告らせたい 天才たちの恋愛頭脳戦",
+            ),
+            ("ﾎﾞｯﾁ・ｻﾞ・ﾛｯｸ！", "ボッチ ザ ロック"),
+            ("呪術廻戦　第２期", "呪術廻戦 第2期"),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(normalize_title(input), expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn keeps_release_tag_words_outside_of_tags() {
+        for input in ["Opus.COLORs", "Raw Hero", "Web Ghosts", "Blu Ray Lovers"] {
+            assert_eq!(strip_release_tags(input), input, "{input}");
+        }
+    }
+
+    #[test]
+    fn strips_bracketed_release_tags() {
+        let cases = [
+            ("Sousou no Frieren [1080p]", "Sousou no Frieren"),
+            ("Sousou no Frieren (BD 1080p HEVC)", "Sousou no Frieren"),
+            ("Sousou no Frieren [WEB-DL][AAC]", "Sousou no Frieren"),
+            ("Sousou no Frieren [1A2B3C4D]", "Sousou no Frieren"),
+            ("葬送のフリーレン (BD 1080p)", "葬送のフリーレン"),
+            // Not only release tags
+            ("Opus.COLORs (TV)", "Opus.COLORs (TV)"),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(strip_release_tags(input), expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn strips_release_tags_after_episode_marker() {
+        let cases = [
+            ("Raw Hero - 05 WEB 1080p", "Raw Hero - 05"),
+            ("Opus.COLORs EP03 BDRip x265", "Opus.COLORs EP03"),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(strip_release_tags(input), expected, "{input}");
+        }
+    }
+}
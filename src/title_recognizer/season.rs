@@ -0,0 +1,198 @@
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::title_recognizer::Title;
+
+/// "Show: Part 2", "Show Cour 2", "Show 2nd Part"
+const PART_PATTERN: &str = r"(?i)[\s:,\-]+(?:(?:part|cour) (?P<number>\d+|[ivx]+)|(?P<ordinal>\d+)(?:st|nd|rd|th) (?:part|cour))$";
+/// "Show: The Final Season", "Show Final Season"
+const FINAL_SEASON_PATTERN: &str = r"(?i)[\s:,\-]+(?:the )?final season$";
+/// "Show 2nd Season", "Show Season 2", "Show Season III", "Show S2",
+/// "Show II", "Show 2期", "Show III期"
+///
+/// "II" is the only roman numeral accepted on its own; the other ones are
+/// way too common as a part of the title (e.g. "Lupin III", "Final Fantasy
+/// VII" or "Gundam X"), so they need to follow "Season" or precede "期".
+const SEASON_PATTERN: &str = r"(?:(?i:[\s:,\-]+(?:(?P<ordinal>\d+)(?:st|nd|rd|th) season|season (?P<number>\d+|[ivx]+)|s(?P<short>\d+)))|[\s:,\-]+(?P<roman>II)|\s*第?(?P<japanese>\d+|[IVX]+)期)$";
+
+static PART_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(PART_PATTERN).unwrap());
+static FINAL_SEASON_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(FINAL_SEASON_PATTERN).unwrap());
+static SEASON_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(SEASON_PATTERN).unwrap());
+
+/// Moves the season and part suffixes of the title (such as "2nd Season" or
+/// "Part 2") into the season and part numbers, so that the title can be
+/// searched for. Season or part numbers that are already set (e.g. by anitomy
+/// or from metadata) are left alone.
+pub fn split_season_suffix(title: &mut Title) {
+    if title.part_number == 1
+        && let Some(captures) = PART_REGEX.captures(&title.title)
+        && let Some(part_number) = captures
+            .name("number")
+            .or(captures.name("ordinal"))
+            .and_then(|x| parse_number(x.as_str()))
+        && let Some(stripped) = strip(&title.title, captures.get(0).unwrap().start())
+    {
+        title.part_number = part_number;
+        title.title = stripped;
+    }
+
+    if title.season_number != 1 || title.final_season {
+        return;
+    }
+
+    if let Some(captures) = FINAL_SEASON_REGEX.captures(&title.title)
+        && let Some(stripped) = strip(&title.title, captures.get(0).unwrap().start())
+    {
+        title.final_season = true;
+        title.title = stripped;
+    } else if let Some(captures) = SEASON_REGEX.captures(&title.title)
+        && let Some(season_number) = ["ordinal", "number", "short", "roman", "japanese"]
+            .iter()
+            .find_map(|x| captures.name(x))
+            .and_then(|x| parse_number(x.as_str()))
+        && let Some(stripped) = strip(&title.title, captures.get(0).unwrap().start())
+    {
+        title.season_number = season_number;
+        title.title = stripped;
+    }
+}
+
+/// Returns the title without the suffix starting at `index`, or `None` if
+/// nothing would be left of it.
+fn strip(title: &str, index: usize) -> Option<String> {
+    let stripped = title[..index].trim_end_matches([' ', ':', ',', '-']);
+
+    (!stripped.is_empty()).then(|| stripped.to_owned())
+}
+
+fn parse_number(number: &str) -> Option<i32> {
+    let number = match number.to_ascii_uppercase().as_str() {
+        "I" => 1,
+        "II" => 2,
+        "III" => 3,
+        "IV" => 4,
+        "V" => 5,
+        "VI" => 6,
+        "VII" => 7,
+        "VIII" => 8,
+        "IX" => 9,
+        "X" => 10,
+        number => number.parse().ok()?,
+    };
+
+    (number >= 1).then_some(number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(title: &str) -> Title {
+        let mut title = Title::new(title.to_owned(), 1, 1);
+        split_season_suffix(&mut title);
+        title
+    }
+
+    #[test]
+    fn splits_season_suffixes() {
+        let cases = [
+            ("Mushoku Tensei 2nd Season", "Mushoku Tensei", 2),
+            ("Spy x Family Season 2", "Spy x Family", 2),
+            ("Spy x Family season III", "Spy x Family", 3),
+            ("Oshi no Ko S2", "Oshi no Ko", 2),
+            ("Overlord II", "Overlord", 2),
+            ("Overlord: II", "Overlord", 2),
+            ("呪術廻戦 2期", "呪術廻戦", 2),
+            ("ゆるキャン△ 第3期", "ゆるキャン△", 3),
+            ("進撃の巨人 III期", "進撃の巨人", 3),
+        ];
+
+        for (input, title, season_number) in cases {
+            let result = split(input);
+            assert_eq!(
+                (result.title.as_str(), result.season_number),
+                (title, season_number),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn keeps_numerals_that_are_part_of_the_title() {
+        for input in [
+            "Lupin III",
+            "Final Fantasy VII",
+            "Mobile Suit Gundam X",
+            "Gundam Build Fighters VIII",
+            "Steins;Gate 0",
+            "II",
+        ] {
+            let result = split(input);
+            assert_eq!(
+                (result.title.as_str(), result.season_number),
+                (input, 1),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn splits_part_suffixes() {
+        let cases = [
+            (
+                "Shingeki no Kyojin Season 3 Part 2",
+                "Shingeki no Kyojin",
+                3,
+                2,
+            ),
+            ("Vinland Saga: Part 2", "Vinland Saga", 1, 2),
+            ("Dr. Stone Cour 2", "Dr. Stone", 1, 2),
+            ("Bleach 2nd Part", "Bleach", 1, 2),
+            ("Re:Zero Part II", "Re:Zero", 1, 2),
+        ];
+
+        for (input, title, season_number, part_number) in cases {
+            let result = split(input);
+            assert_eq!(
+                (
+                    result.title.as_str(),
+                    result.season_number,
+                    result.part_number
+                ),
+                (title, season_number, part_number),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn splits_final_season() {
+        for input in [
+            "Shingeki no Kyojin: The Final Season",
+            "Shingeki no Kyojin Final Season",
+        ] {
+            let result = split(input);
+            assert_eq!(result.title, "Shingeki no Kyojin", "{input}");
+            assert!(result.final_season, "{input}");
+            assert_eq!(result.season_number, 1, "{input}");
+        }
+
+        let result = split("Shingeki no Kyojin: The Final Season Part 2");
+        assert_eq!(result.title, "Shingeki no Kyojin");
+        assert!(result.final_season);
+        assert_eq!(result.part_number, 2);
+    }
+
+    #[test]
+    fn keeps_existing_season_number() {
+        let mut title = Title::new("Overlord II".to_owned(), 3, 1);
+        split_season_suffix(&mut title);
+
+        assert_eq!(
+            (title.title.as_str(), title.season_number),
+            ("Overlord II", 3)
+        );
+    }
+}
@@ -0,0 +1,318 @@
+use std::str::FromStr;
+use std::sync::LazyLock;
+
+use log::debug;
+use regex::Regex;
+use url::Url;
+
+use crate::title_recognizer::{PlayedMedia, Recognition, Recognizer, Title};
+use crate::url_utils::host_matches;
+
+/// "Frieren: S1 E5", "Frieren Season 1 Episode 5", "Frieren - S01E05"
+const SEASON_EPISODE_PATTERN: &str = r"(?i)^(?:watch )?(?P<title>.+?)[\s:,\-|]+(?:season|s) ?(?P<season>\d+)[\s:,\-|]*(?:episode|ep\.?|e) ?(?P<episode>\d+)\b";
+/// "Watch Frieren Episode 5", "Frieren, Ep. 5"
+const EPISODE_PATTERN: &str =
+    r"(?i)^(?:watch )?(?P<title>.+?)[\s:,\-|]+(?:episode|ep\.?) ?(?P<episode>\d+)\b";
+/// "Frieren E5"
+const SHORT_EPISODE_PATTERN: &str = r"^(?P<title>.+?)[\s:,\-|]+E(?P<episode>\d+)\b";
+/// "Frieren 第5話"
+const JAPANESE_EPISODE_PATTERN: &str = r"^(?P<title>.+?)\s*第(?P<episode>\d+)話";
+
+struct StreamingSite {
+    name: &'static str,
+    hosts: &'static [&'static str],
+    prefixes: &'static [&'static str],
+    suffixes: &'static [&'static str],
+    patterns: &'static [&'static str],
+}
+
+const STREAMING_SITES: &[StreamingSite] = &[
+    StreamingSite {
+        name: "Crunchyroll",
+        hosts: &["crunchyroll.com"],
+        prefixes: &[],
+        suffixes: &[
+            " - Watch on Crunchyroll",
+            " - Crunchyroll",
+            " | Crunchyroll",
+        ],
+        patterns: &[SEASON_EPISODE_PATTERN, EPISODE_PATTERN],
+    },
+    StreamingSite {
+        name: "HIDIVE",
+        hosts: &["hidive.com"],
+        prefixes: &[],
+        suffixes: &[" on HIDIVE", " - HIDIVE", " | HIDIVE"],
+        patterns: &[SEASON_EPISODE_PATTERN, EPISODE_PATTERN],
+    },
+    StreamingSite {
+        name: "Netflix",
+        hosts: &["netflix.com"],
+        prefixes: &[],
+        suffixes: &[" | Netflix", " - Netflix"],
+        patterns: &[SEASON_EPISODE_PATTERN, EPISODE_PATTERN],
+    },
+    StreamingSite {
+        name: "Prime Video",
+        hosts: &["primevideo.com", "amazon.com"],
+        prefixes: &["Prime Video: ", "Watch "],
+        suffixes: &[" | Prime Video", " - Prime Video"],
+        patterns: &[SEASON_EPISODE_PATTERN, EPISODE_PATTERN],
+    },
+    StreamingSite {
+        name: "Disney+",
+        hosts: &["disneyplus.com"],
+        prefixes: &[],
+        suffixes: &[" | Disney+", " - Disney+"],
+        patterns: &[SEASON_EPISODE_PATTERN, EPISODE_PATTERN],
+    },
+    StreamingSite {
+        name: "Hulu",
+        hosts: &["hulu.com"],
+        prefixes: &[],
+        suffixes: &[" Online | Hulu", " | Hulu", " - Hulu"],
+        patterns: &[SEASON_EPISODE_PATTERN, EPISODE_PATTERN],
+    },
+    StreamingSite {
+        name: "Bilibili",
+        hosts: &["bilibili.tv", "bilibili.com"],
+        prefixes: &[],
+        suffixes: &[" - BiliBili", " - bilibili", "_bilibili"],
+        patterns: &[
+            SEASON_EPISODE_PATTERN,
+            EPISODE_PATTERN,
+            SHORT_EPISODE_PATTERN,
+            JAPANESE_EPISODE_PATTERN,
+        ],
+    },
+    StreamingSite {
+        name: "ABEMA",
+        hosts: &["abema.tv"],
+        prefixes: &[],
+        suffixes: &[
+            " | 新作アニメ | 無料動画・見逃し配信を見るなら | ABEMA",
+            " | ABEMA",
+        ],
+        patterns: &[
+            JAPANESE_EPISODE_PATTERN,
+            SEASON_EPISODE_PATTERN,
+            EPISODE_PATTERN,
+        ],
+    },
+];
+
+static COMPILED_SITES: LazyLock<Vec<(&'static StreamingSite, Vec<Regex>)>> = LazyLock::new(|| {
+    STREAMING_SITES
+        .iter()
+        .map(|site| {
+            let patterns = site
+                .patterns
+                .iter()
+                .map(|pattern| Regex::new(pattern).unwrap())
+                .collect();
+            (site, patterns)
+        })
+        .collect()
+});
+
+/// Recognizes titles of videos played in web browsers on legal streaming
+/// sites, such as "Watch Frieren Episode 5 - Crunchyroll".
+#[derive(Default)]
+pub struct StreamingSiteRecognizer;
+
+impl StreamingSiteRecognizer {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    fn find_site(media: &PlayedMedia) -> Option<&'static (&'static StreamingSite, Vec<Regex>)> {
+        let host = media
+            .url
+            .as_deref()
+            .and_then(|url| Url::parse(url).ok())
+            .and_then(|url| url.host_str().map(str::to_owned));
+
+        if let Some(host) = host {
+            COMPILED_SITES
+                .iter()
+                .find(|(site, _)| site.hosts.iter().any(|x| host_matches(&host, x)))
+        } else {
+            let title = media.title.as_deref()?;
+            COMPILED_SITES.iter().find(|(site, _)| {
+                site.suffixes.iter().any(|x| title.ends_with(x))
+                    || site.prefixes.iter().any(|x| title.starts_with(x))
+            })
+        }
+    }
+
+    fn strip_site_name<'a>(site: &StreamingSite, title: &'a str) -> &'a str {
+        let title = site
+            .suffixes
+            .iter()
+            .find_map(|x| title.strip_suffix(x))
+            .unwrap_or(title);
+        site.prefixes
+            .iter()
+            .find_map(|x| title.strip_prefix(x))
+            .unwrap_or(title)
+            .trim()
+    }
+
+    fn match_pattern(pattern: &Regex, title: &str) -> Option<Recognition> {
+        let captures = pattern.captures(title)?;
+
+        let anime_title = captures["title"].trim_end_matches([' ', ':', ',', '-', '|']);
+        let episode_number = i32::from_str(&captures["episode"]).ok()?;
+        let season_number = match captures.name("season") {
+            Some(season) => i32::from_str(season.as_str()).ok()?,
+            None => 1,
+        };
+        if anime_title.is_empty() || episode_number < 1 || season_number < 1 {
+            return None;
+        }
+
+        Some(Recognition::new(
+            Title::new(anime_title.to_owned(), season_number, episode_number),
+            captures.name("season").is_some(),
+            true,
+        ))
+    }
+}
+
+impl Recognizer for StreamingSiteRecognizer {
+    fn name(&self) -> &'static str {
+        "streaming-site"
+    }
+
+    fn reliability(&self) -> f32 {
+        0.7
+    }
+
+    fn recognize(&mut self, media: &PlayedMedia) -> Option<Recognition> {
+        let title = media.title.as_deref()?;
+        let (site, patterns) = Self::find_site(media)?;
+        debug!("Found streaming site: {}", site.name);
+
+        let title = Self::strip_site_name(site, title);
+        patterns
+            .iter()
+            .find_map(|pattern| Self::match_pattern(pattern, title))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recognize(title: &str, url: Option<&str>) -> Option<(String, i32, i32)> {
+        let media = PlayedMedia {
+            title: Some(title.to_owned()),
+            url: url.map(str::to_owned),
+            ..Default::default()
+        };
+
+        StreamingSiteRecognizer::new()
+            .recognize(&media)
+            .map(|x| (x.title.title, x.title.season_number, x.title.episode_number))
+    }
+
+    #[test]
+    fn recognizes_page_titles() {
+        let cases = [
+            (
+                "Watch Frieren: Beyond Journey's End Episode 5 - Phantoms of the Dead - Crunchyroll",
+                "https://www.crunchyroll.com/watch/GG1U2J7PW/phantoms-of-the-dead",
+                ("Frieren: Beyond Journey's End", 1, 5),
+            ),
+            (
+                "Frieren: Beyond Journey's End Season 1 Episode 5 - Watch on Crunchyroll",
+                "https://www.crunchyroll.com/watch/GG1U2J7PW",
+                ("Frieren: Beyond Journey's End", 1, 5),
+            ),
+            (
+                "Oshi no Ko Season 1 Episode 3 on HIDIVE",
+                "https://www.hidive.com/video/123",
+                ("Oshi no Ko", 1, 3),
+            ),
+            (
+                "Cyberpunk: Edgerunners: S1 E3 | Netflix",
+                "https://www.netflix.com/watch/81054853",
+                ("Cyberpunk: Edgerunners", 1, 3),
+            ),
+            (
+                "Prime Video: Vinland Saga - Season 2 Episode 4",
+                "https://www.primevideo.com/detail/0QN9/",
+                ("Vinland Saga", 2, 4),
+            ),
+            (
+                "Tokyo Revengers - S02E01 | Disney+",
+                "https://www.disneyplus.com/video/abc",
+                ("Tokyo Revengers", 2, 1),
+            ),
+            (
+                "Watch Attack on Titan Season 4 Episode 28 Online | Hulu",
+                "https://www.hulu.com/watch/abc",
+                ("Attack on Titan", 4, 28),
+            ),
+            (
+                "Spy x Family E5 - BiliBili",
+                "https://www.bilibili.tv/en/play/1234/5678",
+                ("Spy x Family", 1, 5),
+            ),
+            (
+                "葬送のフリーレン 第5話_bilibili",
+                "https://www.bilibili.com/bangumi/play/ep1",
+                ("葬送のフリーレン", 1, 5),
+            ),
+            (
+                "葬送のフリーレン 第5話 | 新作アニメ | 無料動画・見逃し配信を見るなら | ABEMA",
+                "https://abema.tv/video/episode/123",
+                ("葬送のフリーレン", 1, 5),
+            ),
+        ];
+
+        for (title, url, (expected_title, season, episode)) in cases {
+            assert_eq!(
+                recognize(title, Some(url)),
+                Some((expected_title.to_owned(), season, episode)),
+                "{title}"
+            );
+        }
+    }
+
+    #[test]
+    fn recognizes_site_by_title_without_url() {
+        assert_eq!(
+            recognize(
+                "Watch Attack on Titan Season 4 Episode 28 Online | Hulu",
+                None
+            ),
+            Some(("Attack on Titan".to_owned(), 4, 28))
+        );
+    }
+
+    #[test]
+    fn ignores_other_pages() {
+        let cases = [
+            // Not an episode
+            (
+                "Frieren: Beyond Journey's End - Watch on Crunchyroll",
+                Some("https://www.crunchyroll.com/series/GG5H5XQX4"),
+            ),
+            // Not a streaming site
+            (
+                "Frieren Episode 5 - YouTube",
+                Some("https://www.youtube.com/watch?v=abc"),
+            ),
+            (
+                "Frieren Episode 5 - Crunchyroll",
+                Some("https://notcrunchyroll.com/watch"),
+            ),
+            ("Frieren Episode 5", None),
+        ];
+
+        for (title, url) in cases {
+            assert_eq!(recognize(title, url), None, "{title}");
+        }
+    }
+}
@@ -0,0 +1,25 @@
+/// Returns whether `host` is `domain` or one of its subdomains, ignoring
+/// case, e.g. "www.crunchyroll.com" for "crunchyroll.com".
+pub fn host_matches(host: &str, domain: &str) -> bool {
+    let host = host.to_ascii_lowercase();
+    let domain = domain.to_ascii_lowercase();
+
+    host == domain
+        || host
+            .strip_suffix(&domain)
+            .is_some_and(|subdomain| subdomain.ends_with('.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_domain_and_subdomains() {
+        assert!(host_matches("crunchyroll.com", "crunchyroll.com"));
+        assert!(host_matches("www.Crunchyroll.com", "crunchyroll.com"));
+        assert!(host_matches("crunchyroll.com", "Crunchyroll.com"));
+        assert!(!host_matches("notcrunchyroll.com", "crunchyroll.com"));
+        assert!(!host_matches("crunchyroll.com.example", "crunchyroll.com"));
+    }
+}
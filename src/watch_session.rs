@@ -0,0 +1,209 @@
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+/// Playback of a single track by a player, used to tell how much of it
+/// has actually been watched.
+///
+/// Only the time in which the playhead has advanced is counted, so neither
+/// seeking forward nor the time the track was paused counts as watching. The
+/// playhead advances faster than real time when playing at a higher rate, so
+/// the watched time is in terms of the track, not the real time.
+#[derive(Debug)]
+pub struct WatchSession {
+    track: String,
+    duration: Duration,
+    watched: Duration,
+    last_position: Duration,
+    last_update: Instant,
+    rate: f64,
+}
+
+impl WatchSession {
+    pub fn new(track: String, duration: Duration, position: Duration, rate: f64) -> Self {
+        Self::new_at(track, duration, position, rate, Instant::now())
+    }
+
+    fn new_at(
+        track: String,
+        duration: Duration,
+        position: Duration,
+        rate: f64,
+        now: Instant,
+    ) -> Self {
+        Self {
+            track,
+            duration,
+            watched: Duration::ZERO,
+            last_position: position,
+            last_update: now,
+            rate: Self::sanitize_rate(rate),
+        }
+    }
+
+    /// Returns whether this session is for given track.
+    pub fn is_for(&self, track: &str) -> bool {
+        self.track == track
+    }
+
+    /// Records that the track is being played at given position and rate.
+    pub fn update(&mut self, position: Duration, rate: f64) {
+        self.update_at(position, rate, Instant::now());
+    }
+
+    fn update_at(&mut self, position: Duration, rate: f64, now: Instant) {
+        let elapsed = now.duration_since(self.last_update).mul_f64(self.rate);
+        let advanced = position.saturating_sub(self.last_position);
+        self.watched += elapsed.min(advanced);
+
+        self.last_position = position;
+        self.last_update = now;
+        self.rate = Self::sanitize_rate(rate);
+    }
+
+    /// Records that the track is paused, so that neither the time it stays
+    /// paused nor seeking in the meantime counts as watching once it is
+    /// resumed.
+    pub fn pause(&mut self, position: Option<Duration>) {
+        self.pause_at(position, Instant::now());
+    }
+
+    fn pause_at(&mut self, position: Option<Duration>, now: Instant) {
+        if let Some(position) = position {
+            self.last_position = position;
+        }
+        self.last_update = now;
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    pub fn watched(&self) -> Duration {
+        self.watched
+    }
+
+    /// Rewinding and bogus rates are treated as normal playback, as the
+    /// playhead does not advance anyway.
+    fn sanitize_rate(rate: f64) -> f64 {
+        if rate.is_finite() && rate > 0.0 {
+            rate
+        } else {
+            1.0
+        }
+    }
+
+    /// Returns the rate the track was last played at.
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DURATION: Duration = Duration::from_secs(24 * 60);
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    /// Returns a session started at position 0 and the time it was started.
+    fn session(rate: f64) -> (WatchSession, Instant) {
+        let start = Instant::now();
+        let session = WatchSession::new_at("track".to_owned(), DURATION, secs(0), rate, start);
+
+        (session, start)
+    }
+
+    #[test]
+    fn counts_playback() {
+        let (mut session, start) = session(1.0);
+
+        session.update_at(secs(10), 1.0, start + secs(10));
+        session.update_at(secs(30), 1.0, start + secs(30));
+
+        assert_eq!(session.watched(), secs(30));
+    }
+
+    #[test]
+    fn does_not_count_seeking_forward() {
+        let (mut session, start) = session(1.0);
+
+        session.update_at(secs(10), 1.0, start + secs(10));
+        session.update_at(secs(600), 1.0, start + secs(20));
+
+        assert_eq!(session.watched(), secs(20));
+    }
+
+    #[test]
+    fn does_not_count_seeking_back() {
+        let (mut session, start) = session(1.0);
+
+        session.update_at(secs(300), 1.0, start + secs(300));
+        session.update_at(secs(100), 1.0, start + secs(310));
+        session.update_at(secs(120), 1.0, start + secs(330));
+
+        assert_eq!(session.watched(), secs(320));
+    }
+
+    #[test]
+    fn does_not_count_pauses() {
+        let (mut session, start) = session(1.0);
+
+        session.update_at(secs(60), 1.0, start + secs(60));
+        session.pause_at(Some(secs(60)), start + secs(600));
+        session.update_at(secs(70), 1.0, start + secs(610));
+
+        assert_eq!(session.watched(), secs(70));
+    }
+
+    #[test]
+    fn counts_playback_after_resuming() {
+        let (mut session, start) = session(1.0);
+
+        session.update_at(secs(60), 1.0, start + secs(60));
+        session.pause_at(Some(secs(60)), start + secs(70));
+        session.pause_at(Some(secs(60)), start + secs(3600));
+        // The first check after resuming only knows it is playing again
+        session.update_at(secs(65), 1.0, start + secs(3605));
+        session.update_at(secs(125), 1.0, start + secs(3665));
+
+        assert_eq!(session.watched(), secs(125));
+    }
+
+    #[test]
+    fn counts_track_time_at_higher_rate() {
+        let (mut session, start) = session(2.0);
+
+        session.update_at(secs(20), 2.0, start + secs(10));
+        assert_eq!(session.watched(), secs(20));
+
+        // Seeking forward is still not counted
+        session.update_at(secs(600), 2.0, start + secs(20));
+        assert_eq!(session.watched(), secs(40));
+    }
+
+    #[test]
+    fn uses_rate_since_last_update() {
+        let (mut session, start) = session(1.0);
+
+        session.update_at(secs(10), 2.0, start + secs(10));
+        session.update_at(secs(30), 2.0, start + secs(20));
+
+        assert_eq!(session.watched(), secs(30));
+        assert_eq!(session.rate(), 2.0);
+    }
+
+    #[test]
+    fn treats_bogus_rates_as_normal_playback() {
+        for rate in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            let (mut session, start) = session(rate);
+            assert_eq!(session.rate(), 1.0, "{rate}");
+
+            session.update_at(secs(20), rate, start + secs(10));
+            assert_eq!(session.watched(), secs(10), "{rate}");
+        }
+    }
+}
@@ -0,0 +1,251 @@
+use std::time::Duration;
+
+use log::debug;
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+
+use crate::player_controller::{RemotePlayback, RemotePlaybacks};
+use crate::title_recognizer::Title;
+
+const DESKTOP_ENTRY: &str = "jellyfin";
+
+/// Notification sent by the Jellyfin webhook plugin, either with "Send All
+/// Properties" enabled or using a template with the same field names. Since
+/// templates often quote all the values, numbers and booleans are also
+/// accepted as strings.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub(super) struct JellyfinPayload {
+    /// "PlaybackStart", "PlaybackProgress", "PlaybackStop"...
+    notification_type: String,
+    /// "Episode", "Movie"...
+    item_type: String,
+    name: String,
+    series_name: String,
+    #[serde(deserialize_with = "lenient_number")]
+    season_number: Option<i64>,
+    #[serde(deserialize_with = "lenient_number")]
+    episode_number: Option<i64>,
+    #[serde(deserialize_with = "lenient_number")]
+    playback_position_ticks: Option<i64>,
+    #[serde(deserialize_with = "lenient_number")]
+    run_time_ticks: Option<i64>,
+    #[serde(deserialize_with = "lenient_bool")]
+    is_paused: bool,
+    #[serde(deserialize_with = "lenient_bool")]
+    played_to_completion: bool,
+    device_id: String,
+    device_name: String,
+    client_name: String,
+}
+
+impl JellyfinPayload {
+    fn player_id(&self) -> String {
+        let device = if self.device_id.is_empty() {
+            &self.device_name
+        } else {
+            &self.device_id
+        };
+
+        format!("jellyfin:{device}")
+    }
+
+    /// Returns the reported playback, or `None` if it is not an episode nor
+    /// a movie. Specials (season 0) are skipped, as they are numbered
+    /// differently than on MAL.
+    fn playback(&self, watched: bool) -> Option<RemotePlayback> {
+        let title = match self.item_type.as_str() {
+            "Episode" if !self.series_name.is_empty() => {
+                let season = self.season_number.and_then(|x| i32::try_from(x).ok());
+                let episode = self.episode_number.and_then(|x| i32::try_from(x).ok());
+                Title::new(
+                    self.series_name.clone(),
+                    season.filter(|x| *x > 0)?,
+                    episode.filter(|x| *x > 0)?,
+                )
+            }
+            "Movie" if !self.name.is_empty() => {
+                let mut title = Title::new(self.name.clone(), 1, 1);
+                title.movie = true;
+                title
+            }
+            _ => return None,
+        };
+
+        let device = [&self.device_name, &self.client_name]
+            .into_iter()
+            .find(|x| !x.is_empty())
+            .map_or("unknown device", String::as_str);
+
+        Some(RemotePlayback {
+            player_name: format!("Jellyfin ({device})"),
+            desktop_entry: DESKTOP_ENTRY.to_owned(),
+            title,
+            media_title: self.name.clone(),
+            duration: self.run_time_ticks.map(ticks_to_duration),
+            position: self
+                .playback_position_ticks
+                .map(ticks_to_duration)
+                .unwrap_or_default(),
+            playing: !watched && !self.is_paused,
+            watched,
+        })
+    }
+}
+
+pub(super) fn handle(payload: JellyfinPayload, playbacks: &RemotePlaybacks) {
+    let id = payload.player_id();
+
+    let watched = match payload.notification_type.as_str() {
+        "PlaybackStart" | "PlaybackProgress" => false,
+        "PlaybackStop" if payload.played_to_completion => true,
+        "PlaybackStop" => {
+            playbacks.stop(&id);
+            return;
+        }
+        notification_type => {
+            debug!("Ignoring Jellyfin {notification_type} notification");
+            return;
+        }
+    };
+
+    match payload.playback(watched) {
+        Some(playback) => playbacks.update(id, playback),
+        None => debug!("Ignoring Jellyfin playback of {}", payload.name),
+    }
+}
+
+/// Jellyfin times are in ticks of 100 nanoseconds
+fn ticks_to_duration(ticks: i64) -> Duration {
+    Duration::from_nanos((ticks.max(0) as u64).saturating_mul(100))
+}
+
+fn lenient_number<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<i64>, D::Error> {
+    Ok(match Value::deserialize(deserializer)? {
+        Value::Number(number) => number.as_i64(),
+        Value::String(string) => string.trim().parse().ok(),
+        _ => None,
+    })
+}
+
+fn lenient_bool<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    Ok(match Value::deserialize(deserializer)? {
+        Value::Bool(bool) => bool,
+        Value::String(string) => string.trim().eq_ignore_ascii_case("true"),
+        _ => false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    const MINUTE_TICKS: i64 = 60 * 10_000_000;
+
+    #[test]
+    fn parses_all_properties_payload() {
+        let payload: JellyfinPayload = serde_json::from_value(json!({
+            "ServerId": "5e4c7f3a",
+            "ServerName": "media",
+            "ServerVersion": "10.9.11",
+            "ServerUrl": "http://media:8096",
+            "NotificationType": "PlaybackProgress",
+            "Timestamp": "2024-05-01T20:00:00.0000000+02:00",
+            "UtcTimestamp": "2024-05-01T18:00:00.0000000Z",
+            "Name": "The Journey's End",
+            "Overview": "",
+            "ItemId": "a1b2c3",
+            "ItemType": "Episode",
+            "RunTimeTicks": 24 * MINUTE_TICKS,
+            "RunTime": "00:24:00",
+            "Year": 2023,
+            "SeriesName": "Frieren: Beyond Journey's End",
+            "SeasonNumber": 1,
+            "SeasonNumber00": "01",
+            "EpisodeNumber": 1,
+            "EpisodeNumber00": "01",
+            "Provider_tvdb": "424536",
+            "PlaybackPositionTicks": 5 * MINUTE_TICKS,
+            "PlaybackPosition": "00:05:00",
+            "IsPaused": false,
+            "PlayedToCompletion": false,
+            "DeviceId": "tv-1",
+            "DeviceName": "Living Room TV",
+            "ClientName": "Jellyfin Android TV",
+            "UserId": "u1",
+            "NotificationUsername": "user",
+        }))
+        .unwrap();
+
+        assert_eq!(payload.player_id(), "jellyfin:tv-1");
+        let playback = payload.playback(false).unwrap();
+        assert_eq!(playback.player_name, "Jellyfin (Living Room TV)");
+        assert_eq!(
+            playback.title,
+            Title::new("Frieren: Beyond Journey's End".to_owned(), 1, 1)
+        );
+        assert_eq!(playback.media_title, "The Journey's End");
+        assert_eq!(playback.duration, Some(Duration::from_secs(24 * 60)));
+        assert_eq!(playback.position, Duration::from_secs(5 * 60));
+        assert!(playback.playing);
+        assert!(!playback.watched);
+    }
+
+    #[test]
+    fn parses_templated_payload_with_strings() {
+        let payload: JellyfinPayload = serde_json::from_value(json!({
+            "NotificationType": "PlaybackProgress",
+            "ItemType": "Episode",
+            "Name": "Episode 5",
+            "SeriesName": "Sousou no Frieren",
+            "SeasonNumber": "2",
+            "EpisodeNumber": " 5 ",
+            "RunTimeTicks": (24 * MINUTE_TICKS).to_string(),
+            "PlaybackPositionTicks": "",
+            "IsPaused": "True",
+            "PlayedToCompletion": "false",
+            "DeviceName": "Living Room TV",
+        }))
+        .unwrap();
+
+        assert_eq!(payload.player_id(), "jellyfin:Living Room TV");
+        let playback = payload.playback(false).unwrap();
+        assert_eq!(
+            playback.title,
+            Title::new("Sousou no Frieren".to_owned(), 2, 5)
+        );
+        assert_eq!(playback.duration, Some(Duration::from_secs(24 * 60)));
+        assert_eq!(playback.position, Duration::ZERO);
+        assert!(!playback.playing);
+    }
+
+    #[test]
+    fn parses_movies_and_skips_specials() {
+        let movie: JellyfinPayload = serde_json::from_value(json!({
+            "ItemType": "Movie",
+            "Name": "Kimi no Na wa.",
+        }))
+        .unwrap();
+        let title = movie.playback(true).unwrap().title;
+        assert_eq!(title.title, "Kimi no Na wa.");
+        assert!(title.movie);
+
+        let special: JellyfinPayload = serde_json::from_value(json!({
+            "ItemType": "Episode",
+            "SeriesName": "Sousou no Frieren",
+            "SeasonNumber": 0,
+            "EpisodeNumber": 1,
+        }))
+        .unwrap();
+        assert!(special.playback(false).is_none());
+    }
+
+    #[test]
+    fn converts_ticks_without_overflow() {
+        assert_eq!(ticks_to_duration(10_000_000), Duration::from_secs(1));
+        assert_eq!(ticks_to_duration(-1), Duration::ZERO);
+        assert_eq!(ticks_to_duration(i64::MAX), Duration::from_nanos(u64::MAX));
+    }
+}
@@ -0,0 +1,151 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use cot::config::ProjectConfig;
+use cot::form::FormResult;
+use cot::project::RegisterAppsContext;
+use cot::request::Request;
+use cot::request::extractors::{RequestForm, UrlQuery};
+use cot::router::{Route, Router};
+use cot::{App, AppBuilder, Bootstrapper, Project, StatusCode};
+use log::{error, info, warn};
+use serde::Deserialize;
+use tokio::net::TcpListener;
+
+use crate::config::WebhookConfig;
+use crate::player_controller::RemotePlaybacks;
+
+mod jellyfin;
+mod plex;
+
+/// Jellyfin webhook payloads are small
+const MAX_PAYLOAD_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Default, Deserialize)]
+struct WebhookQuery {
+    token: Option<String>,
+}
+
+/// Starts the server receiving the webhooks of media servers in the
+/// background, if it is configured. The playbacks reported by the webhooks
+/// are stored in `playbacks`.
+pub async fn start(config: WebhookConfig, playbacks: Arc<RemotePlaybacks>) -> anyhow::Result<()> {
+    let Some(address) = config.address else {
+        return Ok(());
+    };
+
+    let listener = TcpListener::bind(address)
+        .await
+        .with_context(|| format!("Could not listen for webhooks on {address}"))?;
+    info!("Listening for webhooks on {address}");
+
+    tokio::spawn(async move {
+        if let Err(e) = run(listener, config, playbacks).await {
+            error!("Webhook server error: {e}");
+        }
+    });
+
+    Ok(())
+}
+
+async fn run(
+    listener: TcpListener,
+    config: WebhookConfig,
+    playbacks: Arc<RemotePlaybacks>,
+) -> cot::Result<()> {
+    let token = config.token.clone();
+    let jellyfin_playbacks = playbacks.clone();
+    let jellyfin_handler = async move |UrlQuery(query): UrlQuery<WebhookQuery>,
+                                       request: Request|
+                -> cot::Result<StatusCode> {
+        if !is_authorized(token.as_deref(), &query) {
+            warn!("Rejected a Jellyfin webhook with an invalid token");
+            return Ok(StatusCode::FORBIDDEN);
+        }
+
+        let body = request
+            .into_body()
+            .into_bytes_limited(MAX_PAYLOAD_SIZE)
+            .await?;
+        match serde_json::from_slice(&body) {
+            Ok(payload) => {
+                jellyfin::handle(payload, &jellyfin_playbacks);
+                Ok(StatusCode::NO_CONTENT)
+            }
+            Err(e) => {
+                warn!("Invalid Jellyfin webhook payload: {e}");
+                Ok(StatusCode::BAD_REQUEST)
+            }
+        }
+    };
+
+    let plex_handler = async move |UrlQuery(query): UrlQuery<WebhookQuery>,
+                                   RequestForm(form): RequestForm<plex::PlexWebhookForm>|
+                -> cot::Result<StatusCode> {
+        if !is_authorized(config.token.as_deref(), &query) {
+            warn!("Rejected a Plex webhook with an invalid token");
+            return Ok(StatusCode::FORBIDDEN);
+        }
+
+        let FormResult::Ok(form) = form else {
+            warn!("Plex webhook does not contain the payload");
+            return Ok(StatusCode::BAD_REQUEST);
+        };
+        match serde_json::from_str(&form.payload) {
+            Ok(payload) => {
+                plex::handle(payload, &config.plex_accounts, &playbacks);
+                Ok(StatusCode::NO_CONTENT)
+            }
+            Err(e) => {
+                warn!("Invalid Plex webhook payload: {e}");
+                Ok(StatusCode::BAD_REQUEST)
+            }
+        }
+    };
+
+    #[derive(Clone)]
+    struct WebhookApp {
+        router: Router,
+    }
+
+    impl App for WebhookApp {
+        fn name(&self) -> &str {
+            "webhook_app"
+        }
+
+        fn router(&self) -> Router {
+            self.router.clone()
+        }
+    }
+
+    struct WebhookProject {
+        app: WebhookApp,
+    }
+
+    impl Project for WebhookProject {
+        fn register_apps(&self, apps: &mut AppBuilder, _context: &RegisterAppsContext) {
+            apps.register_with_views(self.app.clone(), "");
+        }
+    }
+
+    let router = Router::with_urls([
+        Route::with_handler("/jellyfin", jellyfin_handler),
+        Route::with_handler("/plex", plex_handler),
+    ]);
+
+    let project = WebhookProject {
+        app: WebhookApp { router },
+    };
+
+    let bootstrapper = Bootstrapper::new(project)
+        .with_config(ProjectConfig::default())
+        .boot()
+        .await?;
+
+    // Not using `run_at`, which would take over Ctrl+C
+    cot::project::run_at_with_shutdown(bootstrapper, listener, std::future::pending()).await
+}
+
+fn is_authorized(token: Option<&str>, query: &WebhookQuery) -> bool {
+    token.is_none_or(|token| query.token.as_deref() == Some(token))
+}
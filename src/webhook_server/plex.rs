@@ -0,0 +1,322 @@
+use std::time::Duration;
+
+use cot::form::Form;
+use log::debug;
+use serde::Deserialize;
+
+use crate::player_controller::{RemotePlayback, RemotePlaybacks};
+use crate::title_recognizer::Title;
+
+const DESKTOP_ENTRY: &str = "plex";
+
+/// Plex sends the webhooks as multipart forms, with the JSON in the `payload`
+/// field (and a thumbnail in the `thumb` field for some events).
+#[derive(Debug, Form)]
+pub(super) struct PlexWebhookForm {
+    pub payload: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub(super) struct PlexPayload {
+    /// "media.play", "media.pause", "media.resume", "media.stop",
+    /// "media.scrobble"...
+    #[serde(rename = "event")]
+    event: String,
+    account: Option<PlexAccount>,
+    player: Option<PlexPlayer>,
+    metadata: Option<PlexMetadata>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PlexAccount {
+    title: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PlexPlayer {
+    title: String,
+    uuid: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct PlexMetadata {
+    /// "episode", "movie"...
+    r#type: String,
+    title: String,
+    /// Title of the show
+    grandparent_title: String,
+    /// Season number
+    parent_index: Option<i32>,
+    /// Episode number
+    index: Option<i32>,
+    /// In milliseconds
+    duration: Option<u64>,
+    /// In milliseconds
+    view_offset: Option<u64>,
+}
+
+impl PlexMetadata {
+    /// Returns the title, or `None` if it is not an episode nor a movie.
+    /// Specials (season 0) are skipped, as they are numbered differently than
+    /// on MAL.
+    fn title(&self) -> Option<Title> {
+        match self.r#type.as_str() {
+            "episode" if !self.grandparent_title.is_empty() => Some(Title::new(
+                self.grandparent_title.clone(),
+                self.parent_index.filter(|x| *x > 0)?,
+                self.index.filter(|x| *x > 0)?,
+            )),
+            "movie" if !self.title.is_empty() => {
+                let mut title = Title::new(self.title.clone(), 1, 1);
+                title.movie = true;
+                Some(title)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Updates the playbacks with given payload. If `accounts` is not empty, the
+/// playbacks of other accounts are ignored.
+pub(super) fn handle(payload: PlexPayload, accounts: &[String], playbacks: &RemotePlaybacks) {
+    let account = payload.account.as_ref().map_or("", |x| x.title.as_str());
+    if !accounts.is_empty() && !accounts.iter().any(|x| x.eq_ignore_ascii_case(account)) {
+        debug!("Ignoring Plex {} event of account {account}", payload.event);
+        return;
+    }
+    let (Some(player), Some(metadata)) = (&payload.player, &payload.metadata) else {
+        debug!("Ignoring Plex {} event without media", payload.event);
+        return;
+    };
+    let id = format!("plex:{}", player.uuid);
+
+    let (playing, watched) = match payload.event.as_str() {
+        "media.play" | "media.resume" => (true, false),
+        "media.pause" => (false, false),
+        "media.scrobble" => (true, true),
+        "media.stop" => {
+            playbacks.stop(&id);
+            return;
+        }
+        event => {
+            debug!("Ignoring Plex {event} event");
+            return;
+        }
+    };
+
+    let Some(title) = metadata.title() else {
+        debug!("Ignoring Plex playback of {}", metadata.title);
+        return;
+    };
+    playbacks.update(
+        id,
+        RemotePlayback {
+            player_name: format!("Plex ({})", player.title),
+            desktop_entry: DESKTOP_ENTRY.to_owned(),
+            title,
+            media_title: metadata.title.clone(),
+            duration: metadata.duration.map(Duration::from_millis),
+            position: Duration::from_millis(metadata.view_offset.unwrap_or_default()),
+            playing,
+            watched,
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use cot::form::{Form, FormResult};
+    use cot::{Body, http};
+    use serde_json::{Value, json};
+
+    use super::*;
+    use crate::player_controller::MediaPlayer;
+
+    fn payload(event: &str, account: &str, metadata: Value) -> PlexPayload {
+        serde_json::from_value(json!({
+            "event": event,
+            "user": true,
+            "owner": true,
+            "Account": {"id": 1, "thumb": "https://plex.tv/users/1/avatar", "title": account},
+            "Server": {"title": "media", "uuid": "server-1"},
+            "Player": {
+                "local": true,
+                "publicAddress": "203.0.113.1",
+                "title": "Living Room TV",
+                "uuid": "player-1",
+            },
+            "Metadata": metadata,
+        }))
+        .unwrap()
+    }
+
+    fn episode() -> Value {
+        json!({
+            "librarySectionType": "show",
+            "ratingKey": "1234",
+            "type": "episode",
+            "title": "The Journey's End",
+            "grandparentTitle": "Frieren: Beyond Journey's End",
+            "parentTitle": "Season 1",
+            "parentIndex": 1,
+            "index": 1,
+            "duration": 1_440_000,
+            "viewOffset": 300_000,
+        })
+    }
+
+    /// Handles given events in order and returns the resulting playback.
+    async fn handle_events(
+        events: &[&str],
+        accounts: &[String],
+        metadata: Value,
+    ) -> Option<(bool, bool, Option<Title>, Option<Duration>, Duration)> {
+        let playbacks = RemotePlaybacks::default();
+        for event in events {
+            handle(
+                payload(event, "user", metadata.clone()),
+                accounts,
+                &playbacks,
+            );
+        }
+
+        let player = playbacks.players().into_iter().next()?;
+        assert_eq!(player.id(), "plex:player-1");
+        Some((
+            player.is_currently_playing().await.unwrap(),
+            player.is_watched().await.unwrap(),
+            player.title().await.unwrap(),
+            player.duration().await.ok(),
+            player.position().await.unwrap(),
+        ))
+    }
+
+    #[tokio::test]
+    async fn reads_payload_from_multipart_form() {
+        let boundary = "boundary";
+        let payload = json!({"event": "media.play", "Metadata": episode()});
+        let body = format!(
+            "--{boundary}\r\n\
+            Content-Disposition: form-data; name=\"payload\"\r\n\
+            Content-Type: application/json\r\n\
+            \r\n\
+            {payload}\r\n\
+            --{boundary}\r\n\
+            Content-Disposition: form-data; name=\"thumb\"; filename=\"thumb.jpg\"\r\n\
+            Content-Type: image/jpeg\r\n\
+            \r\n\
+            jpeg\r\n\
+            --{boundary}--\r\n"
+        );
+        let mut request = http::Request::builder()
+            .method(http::Method::POST)
+            .header(
+                http::header::CONTENT_TYPE,
+                format!("multipart/form-data; boundary={boundary}"),
+            )
+            .body(Body::fixed(body))
+            .unwrap();
+
+        let FormResult::Ok(form) = PlexWebhookForm::from_request(&mut request).await.unwrap()
+        else {
+            panic!("The form is not valid");
+        };
+        let payload: PlexPayload = serde_json::from_str(&form.payload).unwrap();
+
+        assert_eq!(payload.event, "media.play");
+        assert_eq!(
+            payload.metadata.unwrap().title(),
+            Some(Title::new("Frieren: Beyond Journey's End".to_owned(), 1, 1))
+        );
+    }
+
+    #[tokio::test]
+    async fn maps_events_to_playback_state() {
+        let cases = [
+            (&["media.play"][..], Some((true, false))),
+            (&["media.play", "media.pause"], Some((false, false))),
+            (&["media.pause", "media.resume"], Some((true, false))),
+            (&["media.play", "media.scrobble"], Some((true, true))),
+            (&["media.play", "media.stop"], None),
+            // Watched playbacks are kept until they expire
+            (&["media.scrobble", "media.stop"], Some((true, true))),
+            (&["library.new"], None),
+        ];
+
+        for (events, expected) in cases {
+            let state = handle_events(events, &[], episode())
+                .await
+                .map(|(playing, watched, ..)| (playing, watched));
+            assert_eq!(state, expected, "{events:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn reads_title_and_times() {
+        let (_, _, title, duration, position) = handle_events(&["media.play"], &[], episode())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            title,
+            Some(Title::new("Frieren: Beyond Journey's End".to_owned(), 1, 1))
+        );
+        assert_eq!(duration, Some(Duration::from_secs(24 * 60)));
+        assert_eq!(position, Duration::from_secs(5 * 60));
+    }
+
+    #[tokio::test]
+    async fn handles_missing_duration_and_view_offset() {
+        let mut metadata = episode();
+        metadata.as_object_mut().unwrap().remove("duration");
+        metadata.as_object_mut().unwrap().remove("viewOffset");
+
+        let (playing, watched, _, duration, position) =
+            handle_events(&["media.scrobble"], &[], metadata)
+                .await
+                .unwrap();
+
+        assert!(playing && watched);
+        assert_eq!(duration, None);
+        assert_eq!(position, Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn filters_accounts() {
+        let accounts = ["USER".to_owned()];
+        assert!(
+            handle_events(&["media.play"], &accounts, episode())
+                .await
+                .is_some()
+        );
+
+        let accounts = ["someone else".to_owned()];
+        assert!(
+            handle_events(&["media.play"], &accounts, episode())
+                .await
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn reads_movies_and_skips_other_media() {
+        let movie = json!({"type": "movie", "title": "Kimi no Na wa.", "duration": 6_360_000});
+        let (_, _, title, ..) = handle_events(&["media.play"], &[], movie).await.unwrap();
+        let title = title.unwrap();
+        assert_eq!(title.title, "Kimi no Na wa.");
+        assert!(title.movie);
+
+        let special = json!({
+            "type": "episode",
+            "grandparentTitle": "Sousou no Frieren",
+            "parentIndex": 0,
+            "index": 1,
+        });
+        assert!(handle_events(&["media.play"], &[], special).await.is_none());
+
+        let track = json!({"type": "track", "title": "Yuusha"});
+        assert!(handle_events(&["media.play"], &[], track).await.is_none());
+    }
+}